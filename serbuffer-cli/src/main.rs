@@ -0,0 +1,469 @@
+//! `serbuffer` command line tool, inspect the records captured from sockets or state dumps.
+//!
+//! the binary input is a stream of records, each record is prefixed by the length encoded
+//! integer of its length. the schema file is the `Schema` text format, eg:
+//! # order record
+//! id: I64
+//! name: STRING
+
+use std::fs::File;
+use std::io::{BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+
+use clap::{Parser, Subcommand, ValueEnum};
+use serbuffer::csv::{CsvReaderBuilder, CsvWriterBuilder};
+use serbuffer::io::{StreamReader, StreamWriter};
+use serbuffer::{json, Buffer, Schema};
+
+#[derive(Parser)]
+#[command(name = "serbuffer", version, about = "Inspect serbuffer records")]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Print the records of a length prefixed stream
+    Decode {
+        /// Schema file
+        #[arg(long)]
+        schema: PathBuf,
+        /// Annotated hex dump of the fields
+        #[arg(long)]
+        hex: bool,
+        /// Truncate the BYTES values longer than this
+        #[arg(long, default_value_t = 32)]
+        max_bytes: usize,
+        /// Input file, stdin if absent
+        input: Option<PathBuf>,
+    },
+    /// Check every record against the schema
+    Validate {
+        /// Schema file
+        #[arg(long)]
+        schema: PathBuf,
+        /// Input file, stdin if absent
+        input: Option<PathBuf>,
+    },
+    /// Report the record and per field size distributions
+    Stats {
+        /// Schema file
+        #[arg(long)]
+        schema: PathBuf,
+        /// Input file, stdin if absent
+        input: Option<PathBuf>,
+    },
+    /// Convert records between the binary stream, JSON lines and CSV
+    Convert {
+        /// Schema file, must name the fields for JSON and CSV
+        #[arg(long)]
+        schema: PathBuf,
+        #[arg(long, value_enum, default_value_t = Format::Binary)]
+        from: Format,
+        #[arg(long, value_enum)]
+        to: Format,
+        /// Output file, stdout if absent
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        /// Input file, stdin if absent
+        input: Option<PathBuf>,
+    },
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Format {
+    Binary,
+    Json,
+    Csv,
+}
+
+fn main() {
+    let cli = Cli::parse();
+    match run(cli.command) {
+        Ok(true) => {}
+        Ok(false) => std::process::exit(1),
+        // the output is closed by the reader, eg: `| head`
+        Err(e) if e.kind() == std::io::ErrorKind::BrokenPipe => {}
+        Err(e) => {
+            eprintln!("serbuffer: {}", e);
+            std::process::exit(2);
+        }
+    }
+}
+
+/// return `false` if some records are invalid.
+fn run(command: Command) -> Result<bool, std::io::Error> {
+    let stdout = std::io::stdout();
+    let mut out = BufWriter::new(stdout.lock());
+    let valid = match command {
+        Command::Decode {
+            schema,
+            hex,
+            max_bytes,
+            input,
+        } => {
+            let schema = read_schema(&schema)?;
+            decode(
+                &schema,
+                open_input(input.as_deref())?,
+                hex,
+                max_bytes,
+                &mut out,
+            )?;
+            true
+        }
+        Command::Validate { schema, input } => {
+            let schema = read_schema(&schema)?;
+            validate(&schema, open_input(input.as_deref())?, &mut out)?
+        }
+        Command::Stats { schema, input } => {
+            let schema = read_schema(&schema)?;
+            stats(&schema, open_input(input.as_deref())?, &mut out)?
+        }
+        Command::Convert {
+            schema,
+            from,
+            to,
+            output,
+            input,
+        } => {
+            let schema = read_schema(&schema)?;
+            let input = open_input(input.as_deref())?;
+            match output {
+                Some(path) => convert(
+                    &schema,
+                    input,
+                    from,
+                    to,
+                    BufWriter::new(File::create(path)?),
+                )?,
+                None => convert(&schema, input, from, to, &mut out)?,
+            }
+            true
+        }
+    };
+
+    out.flush()?;
+    Ok(valid)
+}
+
+fn decode<R: Read, W: Write>(
+    schema: &Schema,
+    input: R,
+    hex: bool,
+    max_bytes: usize,
+    out: &mut W,
+) -> Result<(), std::io::Error> {
+    for (n, record) in read_records(input).enumerate() {
+        let (offset, buffer) = record?;
+        writeln!(out, "record {} @ {} ({} bytes)", n, offset, buffer.len())?;
+        let display = buffer.display(schema).max_bytes(max_bytes);
+        if hex {
+            write!(out, "{}", display.hex())?;
+        } else {
+            write!(out, "{}", display)?;
+        }
+    }
+    Ok(())
+}
+
+/// report the invalid records, return `false` if any.
+fn validate<R: Read, W: Write>(
+    schema: &Schema,
+    input: R,
+    out: &mut W,
+) -> Result<bool, std::io::Error> {
+    let mut records = 0;
+    let mut invalid = 0;
+    for (n, record) in read_records(input).enumerate() {
+        let (offset, buffer) = record?;
+        records += 1;
+        if let Err(e) = buffer.validate(schema.data_types()) {
+            invalid += 1;
+            writeln!(out, "record {} @ {}: {}", n, offset, e)?;
+        }
+    }
+    writeln!(out, "{} records, {} invalid", records, invalid)?;
+    Ok(invalid == 0)
+}
+
+fn stats<R: Read, W: Write>(
+    schema: &Schema,
+    input: R,
+    out: &mut W,
+) -> Result<bool, std::io::Error> {
+    let mut record_sizes = Vec::new();
+    let mut field_sizes = vec![Vec::new(); schema.len()];
+    let mut invalid = 0;
+    for buffer in StreamReader::new(input) {
+        let buffer = buffer?;
+        if buffer.validate(schema.data_types()).is_err() {
+            invalid += 1;
+            continue;
+        }
+
+        record_sizes.push(buffer.len());
+        let reader = buffer.as_reader(schema);
+        for (index, sizes) in field_sizes.iter_mut().enumerate() {
+            sizes.push(reader.get_bytes_raw(index)?.len());
+        }
+    }
+
+    writeln!(
+        out,
+        "{} records, {} invalid, size {}",
+        record_sizes.len(),
+        invalid,
+        distribution(&mut record_sizes)
+    )?;
+    for (index, sizes) in field_sizes.iter_mut().enumerate() {
+        let data_type = schema.data_types()[index];
+        match schema.field_name(index) {
+            Some(name) => write!(
+                out,
+                "[{}] {}: {}",
+                index,
+                name,
+                serbuffer::types::name(data_type)
+            ),
+            None => write!(out, "[{}] {}", index, serbuffer::types::name(data_type)),
+        }?;
+        writeln!(out, " size {}", distribution(sizes))?;
+    }
+
+    Ok(invalid == 0)
+}
+
+fn distribution(sizes: &mut [usize]) -> String {
+    if sizes.is_empty() {
+        return "-".to_string();
+    }
+
+    sizes.sort_unstable();
+    // nearest rank
+    let percentile = |p: usize| sizes[(sizes.len() * p).div_ceil(100) - 1];
+    let avg = sizes.iter().sum::<usize>() as f64 / sizes.len() as f64;
+    format!(
+        "min {} avg {:.1} p50 {} p99 {} max {}",
+        sizes[0],
+        avg,
+        percentile(50),
+        percentile(99),
+        sizes[sizes.len() - 1]
+    )
+}
+
+fn convert<R: Read, W: Write>(
+    schema: &Schema,
+    input: R,
+    from: Format,
+    to: Format,
+    mut output: W,
+) -> Result<(), std::io::Error> {
+    let buffers: Box<dyn Iterator<Item = Result<Buffer, std::io::Error>>> = match from {
+        Format::Binary => Box::new(StreamReader::new(input)),
+        Format::Json => Box::new(
+            serde_json::Deserializer::from_reader(input)
+                .into_iter::<serde_json::Value>()
+                .map(move |value| json::from_json(&value?, schema)),
+        ),
+        Format::Csv => Box::new(CsvReaderBuilder::new(schema).build(input)),
+    };
+
+    match to {
+        Format::Binary => {
+            let mut writer = StreamWriter::new(output);
+            for buffer in buffers {
+                writer.write(&buffer?)?;
+            }
+            writer.flush()
+        }
+        Format::Json => {
+            for buffer in buffers {
+                writeln!(output, "{}", json::to_json_string(&buffer?, schema)?)?;
+            }
+            output.flush()
+        }
+        Format::Csv => {
+            let mut writer = CsvWriterBuilder::new(schema).build(output)?;
+            for buffer in buffers {
+                writer.write(&buffer?)?;
+            }
+            writer.flush()
+        }
+    }
+}
+
+fn read_schema(path: &Path) -> Result<Schema, std::io::Error> {
+    let text = std::fs::read_to_string(path)?;
+    text.parse().map_err(|e: std::io::Error| {
+        std::io::Error::new(e.kind(), format!("{}: {}", path.display(), e))
+    })
+}
+
+fn open_input(path: Option<&Path>) -> Result<Box<dyn Read>, std::io::Error> {
+    match path {
+        Some(path) => Ok(Box::new(File::open(path)?)),
+        None => Ok(Box::new(std::io::stdin())),
+    }
+}
+
+/// yield the offset and the record of the length prefixed stream.
+fn read_records<R: Read>(input: R) -> impl Iterator<Item = Result<(u64, Buffer), std::io::Error>> {
+    let mut reader = StreamReader::new(input);
+    std::iter::from_fn(move || {
+        let offset = reader.offset();
+        reader
+            .read()
+            .transpose()
+            .map(|r| r.map(|buffer| (offset, buffer)))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use serbuffer::Schema;
+
+    use crate::{convert, decode, distribution, read_records, stats, validate, Format};
+
+    const CSV: &str = "id,name\n1,a\n2,bb\n";
+
+    fn schema() -> Schema {
+        "id: I64\nname: STRING\n".parse().unwrap()
+    }
+
+    /// the records of `CSV` in the length prefixed stream.
+    fn binary(schema: &Schema) -> Vec<u8> {
+        let mut binary = Vec::new();
+        convert(
+            schema,
+            CSV.as_bytes(),
+            Format::Csv,
+            Format::Binary,
+            &mut binary,
+        )
+        .unwrap();
+        binary
+    }
+
+    #[test]
+    pub fn convert_test() {
+        let schema = schema();
+        let binary = binary(&schema);
+        let offsets: Vec<_> = read_records(binary.as_slice())
+            .map(|record| record.unwrap().0)
+            .collect();
+        assert_eq!(offsets, vec![0, 11]);
+
+        let mut output = Vec::new();
+        convert(
+            &schema,
+            binary.as_slice(),
+            Format::Binary,
+            Format::Csv,
+            &mut output,
+        )
+        .unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), CSV);
+
+        let truncated = &binary[..binary.len() - 1];
+        assert!(read_records(truncated).nth(1).unwrap().is_err());
+
+        assert_eq!(
+            distribution(&mut [3, 1, 2]),
+            "min 1 avg 2.0 p50 2 p99 3 max 3"
+        );
+    }
+
+    #[test]
+    pub fn decode_test() {
+        let schema = schema();
+        let binary = binary(&schema);
+
+        let mut out = Vec::new();
+        decode(&schema, binary.as_slice(), false, 32, &mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "record 0 @ 0 (10 bytes)\n[0] id: I64 = 1\n[1] name: BYTES = \"a\"\n\
+             record 1 @ 11 (11 bytes)\n[0] id: I64 = 2\n[1] name: BYTES = \"bb\"\n"
+        );
+
+        let mut out = Vec::new();
+        decode(&schema, &binary[..11], true, 32, &mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "record 0 @ 0 (10 bytes)\n[0] id: I64\n00000000  data  01 00 00 00 00 00 00 00\n\
+             [1] name: BYTES\n00000008  len   01\n00000009  data  61\n"
+        );
+
+        let truncated = &binary[..binary.len() - 1];
+        assert!(decode(&schema, truncated, false, 32, &mut Vec::new()).is_err());
+    }
+
+    #[test]
+    pub fn validate_test() {
+        let schema = schema();
+        let mut binary = binary(&schema);
+
+        let mut out = Vec::new();
+        assert!(validate(&schema, binary.as_slice(), &mut out).unwrap());
+        assert_eq!(String::from_utf8(out).unwrap(), "2 records, 0 invalid\n");
+
+        // a record of 1 byte is shorter than the `I64` field
+        binary.extend_from_slice(&[0x01, 0x01]);
+        let mut out = Vec::new();
+        assert!(!validate(&schema, binary.as_slice(), &mut out).unwrap());
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "record 2 @ 23: unexpected end of file\n3 records, 1 invalid\n"
+        );
+    }
+
+    #[test]
+    pub fn stats_test() {
+        let schema = schema();
+        let mut binary = binary(&schema);
+        binary.extend_from_slice(&[0x01, 0x01]);
+
+        let mut out = Vec::new();
+        assert!(!stats(&schema, binary.as_slice(), &mut out).unwrap());
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "2 records, 1 invalid, size min 10 avg 10.5 p50 10 p99 11 max 11\n\
+             [0] id: I64 size min 8 avg 8.0 p50 8 p99 8 max 8\n\
+             [1] name: BYTES size min 1 avg 1.5 p50 1 p99 2 max 2\n"
+        );
+    }
+
+    #[test]
+    pub fn convert_json_test() {
+        let schema = schema();
+        let binary = binary(&schema);
+
+        let mut json = Vec::new();
+        convert(
+            &schema,
+            binary.as_slice(),
+            Format::Binary,
+            Format::Json,
+            &mut json,
+        )
+        .unwrap();
+        assert_eq!(
+            String::from_utf8(json.clone()).unwrap(),
+            "{\"id\":1,\"name\":\"a\"}\n{\"id\":2,\"name\":\"bb\"}\n"
+        );
+
+        let mut output = Vec::new();
+        convert(
+            &schema,
+            json.as_slice(),
+            Format::Json,
+            Format::Binary,
+            &mut output,
+        )
+        .unwrap();
+        assert_eq!(output, binary);
+    }
+}
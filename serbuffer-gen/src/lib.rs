@@ -5,7 +5,7 @@ use std::io::Write;
 use std::iter::FromIterator;
 use std::path::{Path, PathBuf};
 
-pub const VERSION: &'static str = env!("CARGO_PKG_VERSION");
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
 
 /// code gen
 /// struct Demo {
@@ -63,6 +63,8 @@ impl Codegen {
         let use_script = self.build_use();
         let field_indies = self.build_field_index();
         let data_type = self.build_data_type();
+        let field_name = self.build_field_name();
+        let fixed_size = self.build_fixed_size();
         let field_reader = self.build_field_reader();
         let field_writer = self.build_field_writer();
         let entity = self.build_entity();
@@ -74,7 +76,6 @@ impl Codegen {
 #![allow(unused_attributes)]
 #![cfg_attr(rustfmt, rustfmt_skip)]
 
-#![allow(box_pointers)]
 #![allow(dead_code)]
 #![allow(missing_docs)]
 #![allow(non_camel_case_types)]
@@ -91,12 +92,16 @@ impl Codegen {
 {}
 {}
 {}
+{}
+{}
 "#,
             self.schema,
             VERSION,
             use_script.trim_end(),
             field_indies.trim_end(),
             data_type.trim_end(),
+            field_name.trim_end(),
+            fixed_size.trim_end(),
             field_reader.trim_end(),
             field_writer.trim_end(),
             entity.trim_end(),
@@ -104,7 +109,7 @@ impl Codegen {
     }
 
     fn build_use(&self) -> String {
-        "use serbuffer::{types, BufferReader, BufferWriter, Buffer};\n".to_string()
+        "use serbuffer::{types, BufferReader, BufferWriter, Buffer};\nuse serbuffer::encoding::lenenc_int_len;\n".to_string()
     }
 
     fn build_field_index(&self) -> String {
@@ -155,6 +160,49 @@ pub const FIELD_TYPE: [u8; {}] = [
         script
     }
 
+    fn build_field_name(&self) -> String {
+        let names: Vec<String> = self
+            .fields
+            .iter()
+            .map(|field| format!("    \"{}\",", field.name))
+            .collect();
+
+        format!(
+            r#"
+pub const FIELD_NAMES: [&str; {}] = [
+{}
+];"#,
+            self.fields.len(),
+            names.join("\n"),
+        )
+    }
+
+    fn build_fixed_size(&self) -> String {
+        let fixed_size: Vec<String> = self
+            .fields
+            .iter()
+            .filter(|field| !matches!(field.data_type, DataType::BYTES | DataType::STRING))
+            .map(|field| {
+                format!(
+                    "types::len(types::{}) as usize",
+                    field.data_type.to_string().to_uppercase()
+                )
+            })
+            .collect();
+        let fixed_size = if fixed_size.is_empty() {
+            "0".to_string()
+        } else {
+            fixed_size.join("\n    + ")
+        };
+
+        format!(
+            r#"
+/// the size of the fixed width fields
+pub const FIXED_SIZE: usize = {};"#,
+            fixed_size,
+        )
+    }
+
     fn build_field_reader(&self) -> String {
         let mut field_read_method = "".to_string();
         for index in 0..self.fields.len() {
@@ -162,7 +210,7 @@ pub const FIELD_TYPE: [u8; {}] = [
             let method_script = match field.data_type {
                 DataType::BYTES => format!(
                     r#"
-    pub fn get_{}(&mut self) -> Result<&[u8], std::io::Error> {{
+    pub fn get_{}(&self) -> Result<&[u8], std::io::Error> {{
         self.reader.get_bytes({})
     }}
 "#,
@@ -170,7 +218,7 @@ pub const FIELD_TYPE: [u8; {}] = [
                 ),
                 DataType::STRING => format!(
                     r#"
-    pub fn get_{}(&mut self) -> Result<&str, std::io::Error> {{
+    pub fn get_{}(&self) -> Result<&str, std::io::Error> {{
         self.reader.get_str({})
     }}
 "#,
@@ -178,7 +226,7 @@ pub const FIELD_TYPE: [u8; {}] = [
                 ),
                 _ => format!(
                     r#"
-    pub fn get_{}(&mut self) -> Result<{}, std::io::Error> {{
+    pub fn get_{}(&self) -> Result<{}, std::io::Error> {{
         self.reader.get_{}({})
     }}
 "#,
@@ -196,7 +244,7 @@ pub struct FieldReader<'a> {{
 }}
 
 impl<'a> FieldReader<'a> {{
-    pub fn new(b: &'a mut Buffer) -> Self {{
+    pub fn new(b: &'a Buffer) -> Self {{
         let reader = b.as_reader(&FIELD_TYPE);
         FieldReader {{ reader }}
     }}
@@ -277,6 +325,10 @@ impl<'a> FieldWriter<'a> {{
         }}
     }}
 {}
+
+    pub fn finish(self) -> Result<(), std::io::Error> {{
+        self.writer.finish()
+    }}
 }}"#,
             field_read_method.trim_end()
         )
@@ -287,6 +339,7 @@ impl<'a> FieldWriter<'a> {{
         let mut fields = "".to_string();
         let mut writers = "".to_string();
         let mut readers = "".to_string();
+        let mut lens = "".to_string();
 
         for index in 0..self.fields.len() {
             let field = self.fields.get(index).unwrap();
@@ -295,6 +348,10 @@ impl<'a> FieldWriter<'a> {{
                 DataType::BYTES => {
                     ref_type = true;
                     fields = format!("{}\n    pub {}: &'a [u8],", fields, field.name);
+                    lens = format!(
+                        "{}\n            + lenenc_int_len(self.{}.len() as u64) + self.{}.len()",
+                        lens, field.name, field.name
+                    );
                     writers = format!(
                         "{}\n        writer.set_bytes(self.{})?;",
                         writers, field.name
@@ -307,6 +364,10 @@ impl<'a> FieldWriter<'a> {{
                 DataType::STRING => {
                     ref_type = true;
                     fields = format!("{}\n    pub {}: &'a str,", fields, field.name);
+                    lens = format!(
+                        "{}\n            + lenenc_int_len(self.{}.len() as u64) + self.{}.len()",
+                        lens, field.name, field.name
+                    );
                     writers = format!("{}\n        writer.set_str(self.{})?;", writers, field.name);
                     readers = format!(
                         "{}\n            {}: reader.get_str({})?,",
@@ -342,15 +403,21 @@ pub struct Entity{} {{
 }}
 
 impl{} Entity{} {{
+    /// the exact size of the record written by `to_buffer`.
+    pub fn encoded_len(&self) -> usize {{
+        FIXED_SIZE{}
+    }}
+
     pub fn to_buffer(&self, b: &mut Buffer) -> Result<(), std::io::Error> {{
+        b.reserve(self.encoded_len());
         let mut writer = b.as_writer(&FIELD_TYPE);
         
         {}
 
-        Ok(())
+        writer.finish()
     }}
     
-    pub fn parse(b: &'a mut Buffer) -> Result<Self, std::io::Error> {{
+    pub fn parse(b: &'a Buffer) -> Result<Self, std::io::Error> {{
         let reader = b.as_reader(&FIELD_TYPE);
 
         let entity = Entity {{
@@ -366,6 +433,7 @@ impl{} Entity{} {{
             fields.trim_start(),
             ref_type,
             ref_type,
+            lens,
             writers.trim_start(),
             readers.trim_start()
         )
@@ -483,6 +551,14 @@ mod tests {
             .field("a1", DataType::U8)
             .field("a2", DataType::BYTES)
             .build_script();
+        assert!(script.contains(
+            "pub const FIXED_SIZE: usize = types::len(types::U64) as usize\n    \
+             + types::len(types::BOOL) as usize\n    \
+             + types::len(types::U8) as usize;"
+        ));
+        assert!(
+            script.contains("+ lenenc_int_len(self.agent_id.len() as u64) + self.agent_id.len()")
+        );
 
         println!("-- Start ---");
         println!("{}", script);
@@ -1 +1 @@
-pub mod gen_demo;
\ No newline at end of file
+pub mod gen_demo;
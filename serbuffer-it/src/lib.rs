@@ -2,3 +2,30 @@
 extern crate serde_derive;
 
 pub mod buffer_gen;
+
+#[cfg(test)]
+mod tests {
+    use serbuffer::{Buffer, Schema};
+
+    use crate::buffer_gen::gen_demo::{Entity, FIELD_TYPE, FIXED_SIZE};
+
+    #[test]
+    pub fn encoded_len_test() {
+        assert_eq!(Schema::new(&FIELD_TYPE).unwrap().fixed_size(), FIXED_SIZE);
+
+        for group in ["", "group", &"g".repeat(300)] {
+            let entity = Entity {
+                timestamp: 1,
+                index: 2,
+                group,
+                service: 3,
+                count: 4,
+            };
+
+            let mut buffer = Buffer::with_capacity(0);
+            entity.to_buffer(&mut buffer).unwrap();
+            assert_eq!(buffer.len(), entity.encoded_len());
+            assert_eq!(Entity::parse(&buffer).unwrap().group, group);
+        }
+    }
+}
@@ -1,10 +1,18 @@
 //! https://github.com/capnproto/capnproto-rust/blob/master/capnp/src/lib.rs
 
-use bytes::{BufMut, BytesMut};
+use std::convert::TryFrom;
+use std::io::ErrorKind;
 
+use std::sync::{Arc, OnceLock};
+
+use bytes::{BufMut, Bytes, BytesMut};
+
+use crate::checksum::{crc32c, Checksum};
+use crate::display::BufferDisplay;
 use crate::encoding::read_lenenc_int;
 use crate::reader::{BufferMutReader, BufferReader};
-use crate::writer::BufferWriter;
+use crate::schema::SchemaRef;
+use crate::writer::{BufferWriter, TransactionalBufferWriter, UnorderedBufferWriter};
 
 pub mod types {
     /// types: 0b[type]_[length_mod]
@@ -31,7 +39,7 @@ pub mod types {
     // pub const I = 0b1111;
 
     #[inline]
-    pub fn len(data_type: u8) -> u8 {
+    pub const fn len(data_type: u8) -> u8 {
         let length_mod = data_type & 0b0000_1111;
         if length_mod == 0 {
             1
@@ -39,6 +47,63 @@ pub mod types {
             2 << (length_mod - 1)
         }
     }
+
+    /// the name of the `data_type`, `STRING` is named `BYTES`.
+    pub fn name(data_type: u8) -> &'static str {
+        match data_type {
+            BOOL => "BOOL",
+            I8 => "I8",
+            U8 => "U8",
+            I16 => "I16",
+            U16 => "U16",
+            I32 => "I32",
+            U32 => "U32",
+            I64 => "I64",
+            U64 => "U64",
+            F32 => "F32",
+            F64 => "F64",
+            BYTES => "BYTES",
+            _ => "UNKNOWN",
+        }
+    }
+
+    /// the `data_type` of the name, case insensitive, accept `STRING` as `BYTES`.
+    pub fn from_name(name: &str) -> Option<u8> {
+        let data_type = match name.to_ascii_uppercase().as_str() {
+            "BOOL" => BOOL,
+            "I8" => I8,
+            "U8" => U8,
+            "I16" => I16,
+            "U16" => U16,
+            "I32" => I32,
+            "U32" => U32,
+            "I64" => I64,
+            "U64" => U64,
+            "F32" => F32,
+            "F64" => F64,
+            "BYTES" | "STRING" => BYTES,
+            _ => return None,
+        };
+        Some(data_type)
+    }
+
+    /// check the `data_type` is one of the defined types.
+    #[inline]
+    pub fn is_valid(data_type: u8) -> bool {
+        matches!(
+            data_type,
+            BOOL | I8 | U8 | I16 | U16 | I32 | U32 | I64 | U64 | F32 | F64 | BYTES
+        )
+    }
+
+    /// the size of the fixed width fields, plus a 1 byte length prefix for each `BYTES`.
+    /// the lower bound of a record size, can be used as capacity hint.
+    pub fn min_encoded_size(data_types: &[u8]) -> usize {
+        data_types
+            .iter()
+            .map(|data_type| len(*data_type) as usize)
+            .sum()
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -49,7 +114,8 @@ pub struct Buffer {
     /// field position index cache, build by `Writer` and for `Reader` fast read.
     /// the field is not serialized and deserialized.
     /// must be clear when some create operator such as `new`,`extend`,`reset` ..
-    pub(crate) field_pos_index: Vec<usize>,
+    /// lazy init by the first `Reader` if not build by `Writer`, so read with `&self`.
+    pub(crate) field_pos_index: OnceLock<Vec<usize>>,
 }
 
 impl Buffer {
@@ -57,7 +123,7 @@ impl Buffer {
         Buffer {
             buf: BytesMut::with_capacity(256),
             buf_len: 0,
-            field_pos_index: vec![],
+            field_pos_index: OnceLock::new(),
         }
     }
 
@@ -65,7 +131,7 @@ impl Buffer {
         Buffer {
             buf: BytesMut::with_capacity(capacity),
             buf_len: 0,
-            field_pos_index: vec![],
+            field_pos_index: OnceLock::new(),
         }
     }
 
@@ -74,7 +140,7 @@ impl Buffer {
         Buffer {
             buf: bytes,
             buf_len: buffer_len,
-            field_pos_index: vec![],
+            field_pos_index: OnceLock::new(),
         }
     }
 
@@ -82,12 +148,33 @@ impl Buffer {
         self.buf_len
     }
 
+    pub fn is_empty(&self) -> bool {
+        self.buf_len == 0
+    }
+
+    /// reserve the capacity for at least `additional` more bytes.
+    pub fn reserve(&mut self, additional: usize) {
+        self.buf.reserve(additional);
+    }
+
     pub fn as_slice(&self) -> &[u8] {
         self.buf.as_ref()
     }
 
+    /// clear the data and the field position index, keep the allocated capacity for reuse.
+    pub fn reset(&mut self) {
+        self.buf.clear();
+        self.buf_len = 0;
+        self.field_pos_index.take();
+    }
+
+    /// same as `reset`.
+    pub fn clear(&mut self) {
+        self.reset();
+    }
+
     pub fn extend(&mut self, other: &Buffer) -> Result<(), std::io::Error> {
-        self.field_pos_index.clear();
+        self.field_pos_index.take();
 
         self.buf_len += other.buf_len;
         self.buf.put_slice(other.as_slice());
@@ -95,55 +182,324 @@ impl Buffer {
         Ok(())
     }
 
-    pub fn as_reader<'a, 'b>(&'a mut self, data_types: &'b [u8]) -> BufferReader<'a, 'b> {
-        self.position_index_cache_check(data_types);
+    /// same as `extend`, but check `self` and `other` are exactly filled by the fields of
+    /// `data_types` and `other_data_types` first.
+    pub fn extend_checked(
+        &mut self,
+        data_types: &[u8],
+        other: &Buffer,
+        other_data_types: &[u8],
+    ) -> Result<(), std::io::Error> {
+        self.validate(data_types)?;
+        other.validate(other_data_types)?;
 
-        BufferReader::new(self, data_types)
+        self.extend(other)
     }
 
-    pub fn as_reader_mut<'a, 'b>(&'a mut self, data_types: &'b [u8]) -> BufferMutReader<'a, 'b> {
-        self.position_index_cache_check(data_types);
+    /// check the buffer is exactly filled by the fields of `data_types`.
+    pub fn validate(&self, data_types: &[u8]) -> Result<(), std::io::Error> {
+        let (_, end) = field_positions(&self.buf, data_types)?;
+        if end != self.buf_len {
+            return Err(std::io::Error::new(
+                ErrorKind::InvalidData,
+                format!("the fields end at {} of the {} bytes", end, self.buf_len),
+            ));
+        }
 
-        BufferMutReader::new(self, data_types)
+        Ok(())
     }
 
-    pub fn as_writer<'a, 'b>(&'a mut self, data_types: &'b [u8]) -> BufferWriter<'a, 'b> {
-        self.position_index_cache_check(data_types);
+    /// split the buffer before the field `n`, the fields `[0, n)` in the first buffer
+    /// and `[n, data_types.len())` in the second.
+    /// the two buffers share the memory, no data is copied.
+    pub fn split_at_field(
+        mut self,
+        data_types: &[u8],
+        n: usize,
+    ) -> Result<(Buffer, Buffer), std::io::Error> {
+        if n > data_types.len() {
+            return Err(std::io::Error::from(ErrorKind::InvalidInput));
+        }
+
+        self.try_position_index_cache_check(SchemaRef::from(data_types))?;
+
+        let field_pos_index = self.field_pos_index_mut();
+        let split_pos = field_pos_index.get(n).cloned();
+        let tail_pos_index: Vec<usize> = match split_pos {
+            Some(split_pos) => field_pos_index[n..]
+                .iter()
+                .map(|pos| pos - split_pos)
+                .collect(),
+            None => vec![],
+        };
+        field_pos_index.truncate(n);
+
+        let split_pos = split_pos.unwrap_or(self.buf_len);
+        let tail = Buffer {
+            buf: self.buf.split_off(split_pos),
+            buf_len: self.buf_len - split_pos,
+            field_pos_index: OnceLock::from(tail_pos_index),
+        };
+
+        self.buf_len = split_pos;
+
+        Ok((self, tail))
+    }
+
+    /// concatenate the `buffers` into a new buffer, return it and the merged data types.
+    /// `data_types[i]` is the data types of `buffers[i]`.
+    pub fn join(
+        buffers: &[&Buffer],
+        data_types: &[&[u8]],
+    ) -> Result<(Buffer, Vec<u8>), std::io::Error> {
+        if buffers.len() != data_types.len() {
+            return Err(std::io::Error::from(ErrorKind::InvalidInput));
+        }
+
+        let capacity = buffers.iter().map(|buffer| buffer.len()).sum();
+        let mut joined = Buffer::with_capacity(capacity);
+        let mut joined_types = Vec::new();
+        for (buffer, buffer_types) in buffers.iter().zip(data_types) {
+            joined.extend(buffer)?;
+            joined_types.extend_from_slice(buffer_types);
+        }
 
-        BufferWriter::new(self, data_types)
+        Ok((joined, joined_types))
     }
 
-    fn position_index_cache_check(&mut self, data_types: &[u8]) {
-        if self.field_pos_index.len() == 0 && self.buf_len > 0 {
-            let mut field_start_pos = 0;
-            for index in 0..data_types.len() {
-                if field_start_pos > self.buf_len {
-                    panic!("read error");
-                }
+    /// build a new `Buffer` with the fields of `field_indices`, in that order.
+    /// the raw field bytes are copied without decoding.
+    /// return the new buffer and its data types.
+    pub fn project(
+        &self,
+        data_types: &[u8],
+        field_indices: &[usize],
+    ) -> Result<(Buffer, Vec<u8>), std::io::Error> {
+        let mut target = Buffer::new();
+        let projected_types = self.project_into(data_types, field_indices, &mut target)?;
 
-                self.field_pos_index.push(field_start_pos);
-                let data_type = data_types[index];
-                if data_type == types::BYTES {
-                    let (v, len_length) = read_lenenc_int(&self.buf, field_start_pos).unwrap();
-                    let len = v as usize;
+        Ok((target, projected_types))
+    }
 
-                    field_start_pos += (len + len_length) as usize;
-                } else {
-                    let len = types::len(data_type);
-                    field_start_pos += len as usize;
-                }
+    /// same as `project`, but write the fields into `target`, reuse its allocation.
+    /// the old data of `target` is discarded.
+    pub fn project_into(
+        &self,
+        data_types: &[u8],
+        field_indices: &[usize],
+        target: &mut Buffer,
+    ) -> Result<Vec<u8>, std::io::Error> {
+        let mut projected_types = Vec::with_capacity(field_indices.len());
+        for index in field_indices {
+            match data_types.get(*index) {
+                Some(data_type) => projected_types.push(*data_type),
+                None => return Err(std::io::Error::from(ErrorKind::InvalidInput)),
             }
+        }
 
-            if field_start_pos > self.buf_len {
-                panic!("read error");
+        target.reset();
+
+        {
+            let schema = SchemaRef::from(data_types);
+            let field_pos_index = self.try_position_index_cache_check(schema)?;
+            let reader = BufferReader::new(self.buf.as_ref(), field_pos_index, schema);
+            let mut writer = target.as_writer(projected_types.as_slice());
+            for index in field_indices {
+                writer.set_bytes_raw(reader.get_bytes_raw(*index)?)?;
             }
+            writer.finish()?;
         }
+
+        Ok(projected_types)
+    }
+
+    pub fn as_reader<'a, 'b>(&'a self, schema: impl Into<SchemaRef<'b>>) -> BufferReader<'a, 'b> {
+        let schema = schema.into();
+        let field_pos_index = self.position_index_cache_check(schema);
+
+        BufferReader::new(self.buf.as_ref(), field_pos_index, schema)
+    }
+
+    pub fn as_reader_mut<'a, 'b>(
+        &'a mut self,
+        schema: impl Into<SchemaRef<'b>>,
+    ) -> BufferMutReader<'a, 'b> {
+        let schema = schema.into();
+        self.position_index_cache_check(schema);
+
+        BufferMutReader::new(self, schema)
+    }
+
+    /// writer append the fields of `data_types` to the end of the buffer.
+    /// if the buffer is not empty and no field position index is built, the index is left to
+    /// be built by the next `Reader` with the full data types.
+    pub fn as_writer<'a, 'b>(
+        &'a mut self,
+        schema: impl Into<SchemaRef<'b>>,
+    ) -> BufferWriter<'a, 'b> {
+        BufferWriter::new(self, schema.into().data_types())
+    }
+
+    /// writer continue a record of `data_types`, the buffer already has the first `n` fields.
+    /// fail if the buffer is not exactly filled by the `n` fields.
+    pub fn as_append_writer<'a, 'b>(
+        &'a mut self,
+        schema: impl Into<SchemaRef<'b>>,
+        n: usize,
+    ) -> Result<BufferWriter<'a, 'b>, std::io::Error> {
+        let data_types = schema.into().data_types();
+        let prefix_data_types = data_types
+            .get(..n)
+            .ok_or_else(|| std::io::Error::from(ErrorKind::InvalidInput))?;
+
+        let (field_pos_index, end) = field_positions(&self.buf, prefix_data_types)?;
+        if end != self.buf_len {
+            return Err(std::io::Error::from(ErrorKind::InvalidData));
+        }
+        self.field_pos_index = OnceLock::from(field_pos_index);
+
+        Ok(BufferWriter::new_at(self, data_types, n))
+    }
+
+    /// writer roll back the written fields if it is dropped without `finish` or any `set_*` fail.
+    pub fn as_transactional_writer<'a, 'b>(
+        &'a mut self,
+        schema: impl Into<SchemaRef<'b>>,
+    ) -> TransactionalBufferWriter<'a, 'b> {
+        TransactionalBufferWriter::new(self, schema.into().data_types())
+    }
+
+    /// writer accept the fields in any order, see `UnorderedBufferWriter`.
+    pub fn as_unordered_writer<'a, 'b>(
+        &'a mut self,
+        schema: impl Into<SchemaRef<'b>>,
+    ) -> UnorderedBufferWriter<'a, 'b> {
+        UnorderedBufferWriter::new(self, schema.into().data_types())
+    }
+
+    /// CRC-32C of the data, see `Checksum` for the trailer of the network and disk data.
+    pub fn checksum(&self) -> u32 {
+        crc32c(self.as_slice())
+    }
+
+    /// the data followed by the `checksum` trailer.
+    pub fn into_bytes_with_checksum(self, checksum: Checksum) -> Result<BytesMut, std::io::Error> {
+        let mut buf = self.buf;
+        checksum.append(&mut buf)?;
+        Ok(buf)
+    }
+
+    /// verify and strip the `checksum` trailer, the data is not copied.
+    pub fn try_from_bytes(bytes: BytesMut, checksum: Checksum) -> Result<Self, std::io::Error> {
+        let mut bytes = bytes;
+        checksum.verify(&mut bytes)?;
+        Ok(Buffer::from(bytes))
+    }
+
+    /// build the field position index, then convert to an immutable `FrozenBuffer`.
+    pub fn freeze<'b>(
+        mut self,
+        schema: impl Into<SchemaRef<'b>>,
+    ) -> Result<FrozenBuffer, std::io::Error> {
+        self.try_position_index_cache_check(schema.into())?;
+
+        Ok(FrozenBuffer {
+            buf: self.buf.freeze(),
+            field_pos_index: Arc::new(self.field_pos_index.take().unwrap_or_default()),
+        })
+    }
+
+    /// pretty print the fields by the schema, see `BufferDisplay`.
+    /// eg: println!("{}", buffer.display(&schema).hex())
+    pub fn display<'a, 'b>(&'a self, schema: impl Into<SchemaRef<'b>>) -> BufferDisplay<'a, 'b> {
+        BufferDisplay::new(self.as_slice(), schema.into())
+    }
+
+    pub(crate) fn field_pos_index(&self) -> &[usize] {
+        self.field_pos_index
+            .get()
+            .map(|index| index.as_slice())
+            .unwrap_or_default()
+    }
+
+    pub(crate) fn field_pos_index_mut(&mut self) -> &mut Vec<usize> {
+        self.field_pos_index.get_or_init(Vec::new);
+        self.field_pos_index.get_mut().unwrap()
+    }
+
+    fn position_index_cache_check(&self, schema: SchemaRef) -> &[usize] {
+        self.try_position_index_cache_check(schema)
+            .expect("read error")
+    }
+
+    fn try_position_index_cache_check(
+        &self,
+        schema: SchemaRef,
+    ) -> Result<&[usize], std::io::Error> {
+        if let Some(field_pos_index) = self.field_pos_index.get() {
+            return Ok(field_pos_index.as_slice());
+        }
+
+        let field_pos_index = field_pos_index(&self.buf, schema)?;
+        Ok(self.field_pos_index.get_or_init(|| field_pos_index))
+    }
+}
+
+/// build the field position index of `buf`, empty for an empty `buf`.
+pub(crate) fn field_pos_index(buf: &[u8], schema: SchemaRef) -> Result<Vec<usize>, std::io::Error> {
+    let field_pos_index = match schema.schema() {
+        // the positions of fixed width fields are known without walking the buffer
+        Some(schema) if schema.is_fixed() && !buf.is_empty() => {
+            if types::min_encoded_size(schema.data_types()) > buf.len() {
+                return Err(std::io::Error::from(ErrorKind::UnexpectedEof));
+            }
+            (0..schema.len())
+                .map(|index| schema.fixed_offset(index).unwrap())
+                .collect()
+        }
+        _ if !buf.is_empty() => field_positions(buf, schema.data_types())?.0,
+        _ => vec![],
+    };
+
+    Ok(field_pos_index)
+}
+
+/// walk the fields of `buf` by `data_types`, return the start position of each field
+/// and the end position of the last field.
+fn field_positions(buf: &[u8], data_types: &[u8]) -> Result<(Vec<usize>, usize), std::io::Error> {
+    let mut field_pos_index = Vec::with_capacity(data_types.len());
+    let mut field_start_pos = 0;
+    for data_type in data_types {
+        field_pos_index.push(field_start_pos);
+        let data_type = *data_type;
+        let field_len = if data_type == types::BYTES {
+            let (v, len_length) = read_lenenc_int(buf, field_start_pos)?;
+            usize::try_from(v)
+                .ok()
+                .and_then(|len| len.checked_add(len_length))
+        } else {
+            Some(types::len(data_type) as usize)
+        };
+
+        // the corrupt length prefix may overflow
+        field_start_pos = field_len
+            .and_then(|field_len| field_start_pos.checked_add(field_len))
+            .filter(|end| *end <= buf.len())
+            .ok_or_else(|| std::io::Error::from(ErrorKind::UnexpectedEof))?;
+    }
+
+    Ok((field_pos_index, field_start_pos))
+}
+
+impl Default for Buffer {
+    fn default() -> Self {
+        Buffer::new()
     }
 }
 
 impl std::cmp::PartialEq for Buffer {
     fn eq(&self, other: &Self) -> bool {
-        if self.buf_len != self.buf_len {
+        if self.buf_len != other.buf_len {
             return false;
         }
 
@@ -158,3 +514,78 @@ impl std::hash::Hash for Buffer {
         self.as_slice().hash(state)
     }
 }
+
+/// immutable `Buffer` with a precomputed field position index.
+/// clone is cheap, the data and the index are shared.
+#[derive(Clone, Debug)]
+pub struct FrozenBuffer {
+    buf: Bytes,
+    field_pos_index: Arc<Vec<usize>>,
+}
+
+impl FrozenBuffer {
+    pub fn len(&self) -> usize {
+        self.buf.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.buf.is_empty()
+    }
+
+    pub fn as_slice(&self) -> &[u8] {
+        self.buf.as_ref()
+    }
+
+    pub fn as_reader<'a, 'b>(&'a self, schema: impl Into<SchemaRef<'b>>) -> BufferReader<'a, 'b> {
+        BufferReader::new(
+            self.buf.as_ref(),
+            self.field_pos_index.as_slice(),
+            schema.into(),
+        )
+    }
+
+    /// pretty print the fields by the schema, see `BufferDisplay`.
+    pub fn display<'a, 'b>(&'a self, schema: impl Into<SchemaRef<'b>>) -> BufferDisplay<'a, 'b> {
+        BufferDisplay::new(self.as_slice(), schema.into())
+    }
+
+    /// CRC-32C of the data.
+    pub fn checksum(&self) -> u32 {
+        crc32c(self.as_slice())
+    }
+
+    /// convert back to a mutable `Buffer` without copy, fail if the data is still shared
+    /// with other clones.
+    pub fn try_into_mut(self) -> Result<Buffer, FrozenBuffer> {
+        let field_pos_index = self.field_pos_index;
+        match self.buf.try_into_mut() {
+            Ok(buf) => {
+                let field_pos_index =
+                    Arc::try_unwrap(field_pos_index).unwrap_or_else(|index| index.as_ref().clone());
+                Ok(Buffer {
+                    buf_len: buf.len(),
+                    buf,
+                    field_pos_index: OnceLock::from(field_pos_index),
+                })
+            }
+            Err(buf) => Err(FrozenBuffer {
+                buf,
+                field_pos_index,
+            }),
+        }
+    }
+}
+
+impl std::cmp::PartialEq for FrozenBuffer {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_slice().eq(other.as_slice())
+    }
+}
+
+impl std::cmp::Eq for FrozenBuffer {}
+
+impl std::hash::Hash for FrozenBuffer {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.as_slice().hash(state)
+    }
+}
@@ -0,0 +1,178 @@
+//! checksums to detect the corrupt data.
+//! CRC-32C is always available, xxHash is enabled by the `xxhash` feature.
+
+use std::io::ErrorKind;
+
+/// CRC-32C (Castagnoli) polynomial, reversed.
+const CRC32C_POLY: u32 = 0x82f6_3b78;
+
+const CRC32C_TABLE: [u32; 256] = crc32c_table();
+
+const fn crc32c_table() -> [u32; 256] {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut k = 0;
+        while k < 8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ CRC32C_POLY
+            } else {
+                crc >> 1
+            };
+            k += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+}
+
+/// CRC-32C of the `data`.
+pub fn crc32c(data: &[u8]) -> u32 {
+    crc32c_append(0, data)
+}
+
+/// continue the CRC-32C `crc` of the previous bytes with `data`.
+pub fn crc32c_append(crc: u32, data: &[u8]) -> u32 {
+    let mut crc = !crc;
+    for b in data {
+        crc = CRC32C_TABLE[((crc ^ *b as u32) & 0xff) as usize] ^ (crc >> 8);
+    }
+    !crc
+}
+
+/// the checksum algorithm of the `Buffer` trailer.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Checksum {
+    /// 4 bytes CRC-32C
+    Crc32c,
+    /// 8 bytes XXH3 64 bits, require the `xxhash` feature
+    Xxh3,
+}
+
+impl Checksum {
+    /// the size of the checksum in bytes.
+    pub fn size(&self) -> usize {
+        match self {
+            Checksum::Crc32c => 4,
+            Checksum::Xxh3 => 8,
+        }
+    }
+
+    /// check the algorithm is enabled by the features.
+    pub fn check_enabled(&self) -> Result<(), std::io::Error> {
+        let enabled = match self {
+            Checksum::Crc32c => true,
+            Checksum::Xxh3 => cfg!(feature = "xxhash"),
+        };
+
+        if enabled {
+            Ok(())
+        } else {
+            Err(std::io::Error::new(
+                ErrorKind::Unsupported,
+                format!("checksum {:?} is not enabled by the features", self),
+            ))
+        }
+    }
+
+    pub fn compute(&self, data: &[u8]) -> Result<u64, std::io::Error> {
+        self.check_enabled()?;
+
+        match self {
+            #[cfg(feature = "xxhash")]
+            Checksum::Xxh3 => Ok(xxhash_rust::xxh3::xxh3_64(data)),
+            _ => Ok(crc32c(data) as u64),
+        }
+    }
+
+    /// append the little endian checksum of `data` to `data`.
+    pub(crate) fn append(&self, data: &mut bytes::BytesMut) -> Result<(), std::io::Error> {
+        let checksum = self.compute(data.as_ref())?.to_le_bytes();
+        data.extend_from_slice(&checksum[..self.size()]);
+        Ok(())
+    }
+
+    /// verify and strip the checksum trailer of `data`.
+    pub(crate) fn verify(&self, data: &mut bytes::BytesMut) -> Result<(), std::io::Error> {
+        self.check_enabled()?;
+
+        if data.len() < self.size() {
+            return Err(std::io::Error::new(
+                ErrorKind::UnexpectedEof,
+                format!("{} bytes, no {:?} trailer", data.len(), self),
+            ));
+        }
+
+        let trailer = data.split_off(data.len() - self.size());
+        let mut expected = [0u8; 8];
+        expected[..trailer.len()].copy_from_slice(trailer.as_ref());
+        let expected = u64::from_le_bytes(expected);
+        let actual = self.compute(data.as_ref())?;
+        if actual != expected {
+            data.unsplit(trailer);
+            return Err(std::io::Error::new(
+                ErrorKind::InvalidData,
+                format!(
+                    "{:?} checksum mismatch, expect {:#x}, actual {:#x}",
+                    self, expected, actual
+                ),
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::BytesMut;
+
+    use crate::checksum::{crc32c, crc32c_append, Checksum};
+    use crate::{types, Buffer};
+
+    #[test]
+    pub fn crc32c_test() {
+        // RFC 3720 B.4
+        assert_eq!(crc32c(&[0u8; 32]), 0x8a91_36aa);
+        assert_eq!(crc32c(&[0xffu8; 32]), 0x62a8_ab43);
+        assert_eq!(crc32c(b"123456789"), 0xe306_9283);
+        assert_eq!(crc32c(&[]), 0);
+        assert_eq!(crc32c_append(crc32c(b"1234"), b"56789"), 0xe306_9283);
+    }
+
+    #[test]
+    pub fn buffer_checksum_test() {
+        let data_types = [types::I32, types::BYTES];
+        let mut buffer = Buffer::new();
+        {
+            let mut writer = buffer.as_writer(&data_types);
+            writer.set_i32(7).unwrap();
+            writer.set_str("abc").unwrap();
+            writer.finish().unwrap();
+        }
+        let checksum = buffer.checksum();
+        assert_eq!(checksum, crc32c(buffer.as_slice()));
+
+        for algorithm in [Checksum::Crc32c, Checksum::Xxh3] {
+            if algorithm.check_enabled().is_err() {
+                assert!(buffer.clone().into_bytes_with_checksum(algorithm).is_err());
+                continue;
+            }
+
+            let bytes = buffer.clone().into_bytes_with_checksum(algorithm).unwrap();
+            assert_eq!(bytes.len(), buffer.len() + algorithm.size());
+
+            let restored = Buffer::try_from_bytes(bytes.clone(), algorithm).unwrap();
+            assert_eq!(restored, buffer);
+            assert_eq!(restored.as_reader(&data_types).get_str(1).unwrap(), "abc");
+
+            // flip a bit of the length prefix
+            let mut corrupt = bytes.clone();
+            corrupt[4] ^= 0x01;
+            assert!(Buffer::try_from_bytes(corrupt, algorithm).is_err());
+            assert!(Buffer::try_from_bytes(BytesMut::from(&bytes[..2]), algorithm).is_err());
+        }
+    }
+}
@@ -0,0 +1,199 @@
+//! async record streams over `tokio`, enabled by the `tokio` feature.
+//! each record is prefixed by the length encoded integer of its length.
+
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use bytes::BytesMut;
+use futures_core::Stream;
+use futures_sink::Sink;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio_util::codec::{Decoder, Encoder, FramedRead, FramedWrite};
+
+use crate::encoding::write_lenenc_int;
+use crate::io::{decode_frame, DEFAULT_MAX_RECORD_SIZE};
+use crate::Buffer;
+
+/// length prefix framing of the `Buffer`s, for `tokio_util::codec`.
+#[derive(Clone, Copy, Debug)]
+pub struct BufferCodec {
+    max_record_size: usize,
+}
+
+impl BufferCodec {
+    pub fn new() -> Self {
+        BufferCodec {
+            max_record_size: DEFAULT_MAX_RECORD_SIZE,
+        }
+    }
+
+    /// the larger records are rejected before the allocation.
+    pub fn with_max_record_size(max_record_size: usize) -> Self {
+        BufferCodec { max_record_size }
+    }
+}
+
+impl Default for BufferCodec {
+    fn default() -> Self {
+        BufferCodec::new()
+    }
+}
+
+impl Decoder for BufferCodec {
+    type Item = Buffer;
+    type Error = std::io::Error;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        decode_frame(src, self.max_record_size)
+    }
+}
+
+impl Encoder<Buffer> for BufferCodec {
+    type Error = std::io::Error;
+
+    fn encode(&mut self, item: Buffer, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        self.encode(&item, dst)
+    }
+}
+
+impl<'a> Encoder<&'a Buffer> for BufferCodec {
+    type Error = std::io::Error;
+
+    fn encode(&mut self, item: &'a Buffer, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        dst.reserve(9 + item.len());
+        write_lenenc_int(item.len() as u64, dst);
+        dst.extend_from_slice(item.as_slice());
+        Ok(())
+    }
+}
+
+/// `Stream` of the `Buffer`s read from an `AsyncRead`.
+/// the records are split from the read buffer, no data is copied.
+pub struct AsyncBufferReader<R> {
+    inner: FramedRead<R, BufferCodec>,
+}
+
+impl<R: AsyncRead + Unpin> AsyncBufferReader<R> {
+    pub fn new(reader: R) -> Self {
+        AsyncBufferReader::with_codec(reader, BufferCodec::new())
+    }
+
+    pub fn with_codec(reader: R, codec: BufferCodec) -> Self {
+        AsyncBufferReader {
+            inner: FramedRead::new(reader, codec),
+        }
+    }
+
+    pub fn get_ref(&self) -> &R {
+        self.inner.get_ref()
+    }
+
+    pub fn into_inner(self) -> R {
+        self.inner.into_inner()
+    }
+}
+
+impl<R: AsyncRead + Unpin> Stream for AsyncBufferReader<R> {
+    type Item = Result<Buffer, std::io::Error>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        Pin::new(&mut self.inner).poll_next(cx)
+    }
+}
+
+/// `Sink` of the `Buffer`s written to an `AsyncWrite`.
+/// the records are batched in the write buffer, `poll_ready` wait for the buffer to drain
+/// once it is larger than the backpressure boundary.
+pub struct AsyncBufferWriter<W> {
+    inner: FramedWrite<W, BufferCodec>,
+}
+
+impl<W: AsyncWrite + Unpin> AsyncBufferWriter<W> {
+    pub fn new(writer: W) -> Self {
+        AsyncBufferWriter {
+            inner: FramedWrite::new(writer, BufferCodec::new()),
+        }
+    }
+
+    /// the size of the batched data to start the backpressure, default 128 KiB.
+    pub fn with_backpressure_boundary(writer: W, boundary: usize) -> Self {
+        let mut inner = FramedWrite::new(writer, BufferCodec::new());
+        inner.set_backpressure_boundary(boundary);
+        AsyncBufferWriter { inner }
+    }
+
+    pub fn get_ref(&self) -> &W {
+        self.inner.get_ref()
+    }
+
+    pub fn into_inner(self) -> W {
+        self.inner.into_inner()
+    }
+}
+
+impl<W: AsyncWrite + Unpin> Sink<Buffer> for AsyncBufferWriter<W> {
+    type Error = std::io::Error;
+
+    fn poll_ready(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Sink::<Buffer>::poll_ready(Pin::new(&mut self.inner), cx)
+    }
+
+    fn start_send(mut self: Pin<&mut Self>, item: Buffer) -> Result<(), Self::Error> {
+        Sink::<Buffer>::start_send(Pin::new(&mut self.inner), item)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Sink::<Buffer>::poll_flush(Pin::new(&mut self.inner), cx)
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Sink::<Buffer>::poll_close(Pin::new(&mut self.inner), cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures_util::{SinkExt, StreamExt};
+
+    use crate::codec::{AsyncBufferReader, AsyncBufferWriter, BufferCodec};
+    use crate::test_util::{record, record_named, records, schema};
+
+    #[tokio::test]
+    pub async fn async_stream_test() {
+        let schema = schema();
+        let mut expected = records(&schema, 0..100);
+        expected[50] = record_named(&schema, 50, "x".repeat(70_000).as_str());
+
+        // the small duplex buffer force the backpressure and the partial reads
+        let (client, server) = tokio::io::duplex(64);
+        let buffers = expected.clone();
+        let writer = tokio::spawn(async move {
+            let mut writer = AsyncBufferWriter::with_backpressure_boundary(client, 256);
+            for buffer in buffers {
+                writer.feed(buffer).await.unwrap();
+            }
+            writer.close().await.unwrap();
+        });
+
+        let reader = AsyncBufferReader::new(server);
+        let buffers: Vec<_> = reader.map(|buffer| buffer.unwrap()).collect().await;
+        assert_eq!(buffers, expected);
+        writer.await.unwrap();
+
+        // max record size
+        let (mut client, server) = tokio::io::duplex(1024);
+        let mut reader =
+            AsyncBufferReader::with_codec(server, BufferCodec::with_max_record_size(8));
+        let mut encoded = bytes::BytesMut::new();
+        tokio_util::codec::Encoder::encode(
+            &mut BufferCodec::new(),
+            &record(&schema, 1),
+            &mut encoded,
+        )
+        .unwrap();
+        tokio::io::AsyncWriteExt::write_all(&mut client, encoded.as_ref())
+            .await
+            .unwrap();
+        assert!(reader.next().await.unwrap().is_err());
+    }
+}
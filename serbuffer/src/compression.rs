@@ -0,0 +1,130 @@
+//! block compression of the record files, the codecs are enabled by the `lz4`, `zstd` and
+//! `snappy` features. the records are compressed in blocks, never one by one.
+
+use std::io::ErrorKind;
+
+use bytes::BytesMut;
+
+/// max ratio of the decompressed to the compressed size, a corrupt raw length can not
+/// allocate more. the blocks compressed beyond it are stored uncompressed.
+pub(crate) const MAX_RATIO: usize = 1024;
+
+/// the block compression codec.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum Compression {
+    #[default]
+    None,
+    /// require the `lz4` feature
+    Lz4,
+    /// require the `zstd` feature, with the compression level
+    Zstd(i32),
+    /// require the `snappy` feature
+    Snappy,
+}
+
+impl Compression {
+    /// the codec id in the file header.
+    pub(crate) fn id(&self) -> u8 {
+        match self {
+            Compression::None => 0,
+            Compression::Lz4 => 1,
+            Compression::Zstd(_) => 2,
+            Compression::Snappy => 3,
+        }
+    }
+
+    pub(crate) fn from_id(id: u8) -> Result<Self, std::io::Error> {
+        match id {
+            0 => Ok(Compression::None),
+            1 => Ok(Compression::Lz4),
+            2 => Ok(Compression::Zstd(0)),
+            3 => Ok(Compression::Snappy),
+            _ => Err(std::io::Error::new(
+                ErrorKind::InvalidData,
+                format!("unknown compression codec {}", id),
+            )),
+        }
+    }
+
+    /// check the codec is enabled by the features.
+    pub fn check_enabled(&self) -> Result<(), std::io::Error> {
+        let enabled = match self {
+            Compression::None => true,
+            Compression::Lz4 => cfg!(feature = "lz4"),
+            Compression::Zstd(_) => cfg!(feature = "zstd"),
+            Compression::Snappy => cfg!(feature = "snappy"),
+        };
+
+        if enabled {
+            Ok(())
+        } else {
+            Err(std::io::Error::new(
+                ErrorKind::Unsupported,
+                format!("compression {:?} is not enabled by the features", self),
+            ))
+        }
+    }
+
+    pub(crate) fn compress(&self, data: &[u8]) -> Result<Vec<u8>, std::io::Error> {
+        self.check_enabled()?;
+
+        match self {
+            #[cfg(feature = "lz4")]
+            Compression::Lz4 => Ok(lz4_flex::block::compress(data)),
+            #[cfg(feature = "zstd")]
+            Compression::Zstd(level) => zstd::bulk::compress(data, *level),
+            #[cfg(feature = "snappy")]
+            Compression::Snappy => snap::raw::Encoder::new()
+                .compress_vec(data)
+                .map_err(|e| std::io::Error::new(ErrorKind::InvalidInput, e)),
+            _ => Ok(data.to_vec()),
+        }
+    }
+
+    /// decompress to a new block of `raw_len` bytes.
+    pub(crate) fn decompress(
+        &self,
+        data: &[u8],
+        raw_len: usize,
+    ) -> Result<BytesMut, std::io::Error> {
+        self.check_enabled()?;
+
+        if raw_len > data.len().saturating_mul(MAX_RATIO) {
+            return Err(std::io::Error::new(
+                ErrorKind::InvalidData,
+                format!(
+                    "{} bytes can not decompress to {} bytes",
+                    data.len(),
+                    raw_len
+                ),
+            ));
+        }
+
+        let mut block = BytesMut::zeroed(raw_len);
+        let len = match self {
+            #[cfg(feature = "lz4")]
+            Compression::Lz4 => lz4_flex::block::decompress_into(data, block.as_mut())
+                .map_err(|e| std::io::Error::new(ErrorKind::InvalidData, e))?,
+            #[cfg(feature = "zstd")]
+            Compression::Zstd(_) => zstd::bulk::decompress_to_buffer(data, block.as_mut())?,
+            #[cfg(feature = "snappy")]
+            Compression::Snappy => snap::raw::Decoder::new()
+                .decompress(data, block.as_mut())
+                .map_err(|e| std::io::Error::new(ErrorKind::InvalidData, e))?,
+            _ => {
+                let len = data.len().min(raw_len);
+                block[..len].copy_from_slice(&data[..len]);
+                data.len()
+            }
+        };
+
+        if len != raw_len {
+            return Err(std::io::Error::new(
+                ErrorKind::InvalidData,
+                format!("decompressed {} of {} bytes", len, raw_len),
+            ));
+        }
+
+        Ok(block)
+    }
+}
@@ -0,0 +1,461 @@
+//! import and export records as CSV rows, the columns are parsed by the `types::*` of the schema.
+//! `BYTES` cells are taken as is, the header row is the field names of a named `Schema`.
+
+use std::io::ErrorKind;
+use std::str::FromStr;
+
+use crate::{types, Buffer, Schema, Value};
+
+/// how to fill a cell equal to the null token.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NullHandling {
+    /// fail the row.
+    Error,
+    /// fill the zero value of the type, `false` and empty `BYTES`.
+    Default,
+}
+
+/// build a `CsvReader`
+/// eg: CsvReaderBuilder::new(&schema).null_value("NULL", NullHandling::Default).build(file)
+pub struct CsvReaderBuilder<'a> {
+    schema: &'a Schema,
+    has_headers: bool,
+    delimiter: u8,
+    null_value: Option<(String, NullHandling)>,
+}
+
+impl<'a> CsvReaderBuilder<'a> {
+    pub fn new(schema: &'a Schema) -> Self {
+        CsvReaderBuilder {
+            schema,
+            has_headers: true,
+            delimiter: b',',
+            null_value: None,
+        }
+    }
+
+    /// the first row is the header, the columns are matched to the fields by name.
+    /// without header the columns are in schema order. default `true`.
+    pub fn has_headers(&mut self, has_headers: bool) -> &mut Self {
+        self.has_headers = has_headers;
+        self
+    }
+
+    pub fn delimiter(&mut self, delimiter: u8) -> &mut Self {
+        self.delimiter = delimiter;
+        self
+    }
+
+    /// the cells equal to `token` are null, and filled by `handling`.
+    /// no cell is null by default.
+    pub fn null_value(&mut self, token: &str, handling: NullHandling) -> &mut Self {
+        self.null_value = Some((token.to_string(), handling));
+        self
+    }
+
+    pub fn build<R: std::io::Read>(&self, reader: R) -> CsvReader<'a, R> {
+        let reader = ::csv::ReaderBuilder::new()
+            .has_headers(self.has_headers)
+            .delimiter(self.delimiter)
+            .flexible(true)
+            .from_reader(reader);
+
+        CsvReader {
+            reader,
+            schema: self.schema,
+            has_headers: self.has_headers,
+            null_value: self.null_value.clone(),
+            column_fields: None,
+            record: ::csv::ByteRecord::new(),
+            failed: false,
+        }
+    }
+}
+
+/// stream CSV rows into `Buffer`s.
+pub struct CsvReader<'a, R> {
+    reader: ::csv::Reader<R>,
+    schema: &'a Schema,
+    has_headers: bool,
+    null_value: Option<(String, NullHandling)>,
+    /// the field index of each column
+    column_fields: Option<Vec<usize>>,
+    record: ::csv::ByteRecord,
+    /// stop after a header or an input error, a row error only skips the row
+    failed: bool,
+}
+
+impl<'a, R: std::io::Read> CsvReader<'a, R> {
+    /// read the next row into `buffer`, the `buffer` is reset first.
+    /// return `false` at the end of the input.
+    pub fn read_buffer(&mut self, buffer: &mut Buffer) -> Result<bool, std::io::Error> {
+        if self.failed {
+            return Ok(false);
+        }
+
+        if self.column_fields.is_none() {
+            let column_fields = self.column_fields();
+            self.failed = column_fields.is_err();
+            self.column_fields = Some(column_fields?);
+        }
+
+        let has_record = self.reader.read_byte_record(&mut self.record);
+        self.failed = has_record.is_err();
+        if !has_record.map_err(csv_error)? {
+            return Ok(false);
+        }
+
+        let row = self.record.position().map(|p| p.line()).unwrap_or(0);
+        let column_fields = self.column_fields.as_ref().unwrap();
+        if self.record.len() != column_fields.len() {
+            return Err(std::io::Error::new(
+                ErrorKind::InvalidData,
+                format!(
+                    "row {}: {} columns, expect {}",
+                    row,
+                    self.record.len(),
+                    column_fields.len()
+                ),
+            ));
+        }
+
+        buffer.reset();
+        let mut writer = buffer.as_unordered_writer(self.schema);
+        for (column, (cell, index)) in self.record.iter().zip(column_fields).enumerate() {
+            let value = self
+                .parse_cell(cell, *index)
+                .map_err(|message| cell_error(self.schema, row, column, *index, message))?;
+            writer.set(*index, value)?;
+        }
+        writer.finish()?;
+
+        Ok(true)
+    }
+
+    /// read up to `max_rows` rows, less rows are returned at the end of the input.
+    pub fn read_batch(&mut self, max_rows: usize) -> Result<Vec<Buffer>, std::io::Error> {
+        let mut buffers = Vec::new();
+        while buffers.len() < max_rows {
+            match self.next() {
+                Some(buffer) => buffers.push(buffer?),
+                None => break,
+            }
+        }
+        Ok(buffers)
+    }
+
+    fn column_fields(&mut self) -> Result<Vec<usize>, std::io::Error> {
+        if !self.has_headers {
+            return Ok((0..self.schema.len()).collect());
+        }
+
+        let headers = self.reader.byte_headers().map_err(csv_error)?.clone();
+        let mut column_fields = Vec::with_capacity(headers.len());
+        for (column, header) in headers.iter().enumerate() {
+            let header = String::from_utf8_lossy(header);
+            let index = self.schema.index_of(header.as_ref()).ok_or_else(|| {
+                std::io::Error::new(
+                    ErrorKind::InvalidData,
+                    format!("column {}: unknown field `{}`", column + 1, header),
+                )
+            })?;
+            if column_fields.contains(&index) {
+                return Err(std::io::Error::new(
+                    ErrorKind::InvalidData,
+                    format!("column {}: duplicate field `{}`", column + 1, header),
+                ));
+            }
+            column_fields.push(index);
+        }
+
+        if let Some(index) = (0..self.schema.len()).find(|index| !column_fields.contains(index)) {
+            return Err(std::io::Error::new(
+                ErrorKind::InvalidData,
+                format!(
+                    "field `{}` is missing in the header",
+                    self.schema.field_name(index).unwrap_or_default()
+                ),
+            ));
+        }
+
+        Ok(column_fields)
+    }
+
+    fn parse_cell<'c>(&self, cell: &'c [u8], index: usize) -> Result<Value<'c>, String> {
+        let data_type = self.schema.data_types()[index];
+
+        if let Some((token, handling)) = &self.null_value {
+            if cell == token.as_bytes() {
+                return match handling {
+                    NullHandling::Error => Err("null value".to_string()),
+                    NullHandling::Default => Ok(default_value(data_type)),
+                };
+            }
+        }
+
+        if data_type == types::BYTES {
+            return Ok(Value::Bytes(cell));
+        }
+
+        let s = std::str::from_utf8(cell).map_err(|e| e.to_string())?;
+        let value = match data_type {
+            types::BOOL => Value::Bool(parse(s, data_type)?),
+            types::I8 => Value::I8(parse(s, data_type)?),
+            types::U8 => Value::U8(parse(s, data_type)?),
+            types::I16 => Value::I16(parse(s, data_type)?),
+            types::U16 => Value::U16(parse(s, data_type)?),
+            types::I32 => Value::I32(parse(s, data_type)?),
+            types::U32 => Value::U32(parse(s, data_type)?),
+            types::I64 => Value::I64(parse(s, data_type)?),
+            types::U64 => Value::U64(parse(s, data_type)?),
+            types::F32 => Value::F32(parse(s, data_type)?),
+            types::F64 => Value::F64(parse(s, data_type)?),
+            _ => return Err(format!("unknown data type {:#010b}", data_type)),
+        };
+
+        Ok(value)
+    }
+}
+
+impl<'a, R: std::io::Read> Iterator for CsvReader<'a, R> {
+    type Item = Result<Buffer, std::io::Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut buffer = Buffer::with_capacity(types::min_encoded_size(self.schema.data_types()));
+        match self.read_buffer(&mut buffer) {
+            Ok(true) => Some(Ok(buffer)),
+            Ok(false) => None,
+            Err(e) => Some(Err(e)),
+        }
+    }
+}
+
+/// build a `CsvWriter`
+pub struct CsvWriterBuilder<'a> {
+    schema: &'a Schema,
+    has_headers: bool,
+    delimiter: u8,
+}
+
+impl<'a> CsvWriterBuilder<'a> {
+    pub fn new(schema: &'a Schema) -> Self {
+        CsvWriterBuilder {
+            schema,
+            has_headers: true,
+            delimiter: b',',
+        }
+    }
+
+    /// write the field names as the header row, require a named schema. default `true`.
+    pub fn has_headers(&mut self, has_headers: bool) -> &mut Self {
+        self.has_headers = has_headers;
+        self
+    }
+
+    pub fn delimiter(&mut self, delimiter: u8) -> &mut Self {
+        self.delimiter = delimiter;
+        self
+    }
+
+    pub fn build<W: std::io::Write>(&self, writer: W) -> Result<CsvWriter<'a, W>, std::io::Error> {
+        let mut writer = ::csv::WriterBuilder::new()
+            .has_headers(false)
+            .delimiter(self.delimiter)
+            .from_writer(writer);
+
+        if self.has_headers {
+            let field_names = self.schema.field_names().ok_or_else(|| {
+                std::io::Error::new(
+                    ErrorKind::InvalidInput,
+                    "the CSV header require a named schema",
+                )
+            })?;
+            writer.write_record(field_names).map_err(csv_error)?;
+        }
+
+        Ok(CsvWriter {
+            writer,
+            schema: self.schema,
+            record: ::csv::ByteRecord::new(),
+        })
+    }
+}
+
+/// write `Buffer`s as CSV rows.
+pub struct CsvWriter<'a, W: std::io::Write> {
+    writer: ::csv::Writer<W>,
+    schema: &'a Schema,
+    record: ::csv::ByteRecord,
+}
+
+impl<'a, W: std::io::Write> CsvWriter<'a, W> {
+    pub fn write(&mut self, buffer: &Buffer) -> Result<(), std::io::Error> {
+        buffer.validate(self.schema.data_types())?;
+
+        self.record.clear();
+        for value in buffer.as_reader(self.schema).iter() {
+            match value? {
+                Value::Bool(v) => self.record.push_field(v.to_string().as_bytes()),
+                Value::I8(v) => self.record.push_field(v.to_string().as_bytes()),
+                Value::U8(v) => self.record.push_field(v.to_string().as_bytes()),
+                Value::I16(v) => self.record.push_field(v.to_string().as_bytes()),
+                Value::U16(v) => self.record.push_field(v.to_string().as_bytes()),
+                Value::I32(v) => self.record.push_field(v.to_string().as_bytes()),
+                Value::U32(v) => self.record.push_field(v.to_string().as_bytes()),
+                Value::I64(v) => self.record.push_field(v.to_string().as_bytes()),
+                Value::U64(v) => self.record.push_field(v.to_string().as_bytes()),
+                Value::F32(v) => self.record.push_field(v.to_string().as_bytes()),
+                Value::F64(v) => self.record.push_field(v.to_string().as_bytes()),
+                Value::Bytes(v) => self.record.push_field(v),
+                Value::Str(v) => self.record.push_field(v.as_bytes()),
+            }
+        }
+
+        self.writer
+            .write_byte_record(&self.record)
+            .map_err(csv_error)
+    }
+
+    pub fn flush(&mut self) -> Result<(), std::io::Error> {
+        self.writer.flush()
+    }
+
+    /// flush and return the underlying writer.
+    pub fn into_inner(self) -> Result<W, std::io::Error> {
+        self.writer
+            .into_inner()
+            .map_err(|e| std::io::Error::new(e.error().kind(), e.error().to_string()))
+    }
+}
+
+fn parse<T: FromStr>(s: &str, data_type: u8) -> Result<T, String> {
+    s.parse()
+        .map_err(|_| format!("cannot parse {:?} as {}", s, types::name(data_type)))
+}
+
+fn default_value(data_type: u8) -> Value<'static> {
+    match data_type {
+        types::BOOL => Value::Bool(false),
+        types::I8 => Value::I8(0),
+        types::U8 => Value::U8(0),
+        types::I16 => Value::I16(0),
+        types::U16 => Value::U16(0),
+        types::I32 => Value::I32(0),
+        types::U32 => Value::U32(0),
+        types::I64 => Value::I64(0),
+        types::U64 => Value::U64(0),
+        types::F32 => Value::F32(0.0),
+        types::F64 => Value::F64(0.0),
+        _ => Value::Bytes(&[]),
+    }
+}
+
+fn cell_error(
+    schema: &Schema,
+    row: u64,
+    column: usize,
+    index: usize,
+    message: String,
+) -> std::io::Error {
+    let field = match schema.field_name(index) {
+        Some(name) => format!("`{}`", name),
+        None => format!("{}", index),
+    };
+    std::io::Error::new(
+        ErrorKind::InvalidData,
+        format!(
+            "row {}, column {} (field {}): {}",
+            row,
+            column + 1,
+            field,
+            message
+        ),
+    )
+}
+
+fn csv_error(e: ::csv::Error) -> std::io::Error {
+    std::io::Error::new(ErrorKind::InvalidData, e)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::csv::{CsvReaderBuilder, CsvWriterBuilder, NullHandling};
+    use crate::{types, Schema};
+
+    #[test]
+    pub fn csv_test() {
+        let schema = Schema::named(
+            &["id", "name", "score", "ok"],
+            &[types::I32, types::STRING, types::F64, types::BOOL],
+        )
+        .unwrap();
+
+        let input = "name,id,score,ok\n\"a,b\",1,0.5,true\nc,2,NULL,false\n";
+        let buffers: Vec<_> = CsvReaderBuilder::new(&schema)
+            .null_value("NULL", NullHandling::Default)
+            .build(input.as_bytes())
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(buffers.len(), 2);
+
+        let reader = buffers[0].as_reader(&schema);
+        assert_eq!(reader.get_i32(0).unwrap(), 1);
+        assert_eq!(reader.get_str(1).unwrap(), "a,b");
+        assert_eq!(reader.get_f64(2).unwrap(), 0.5);
+        assert!(reader.get_bool(3).unwrap());
+        assert_eq!(buffers[1].as_reader(&schema).get_f64(2).unwrap(), 0.0);
+
+        let mut writer = CsvWriterBuilder::new(&schema).build(Vec::new()).unwrap();
+        for buffer in &buffers {
+            writer.write(buffer).unwrap();
+        }
+        let output = String::from_utf8(writer.into_inner().unwrap()).unwrap();
+        assert_eq!(
+            output,
+            "id,name,score,ok\n1,\"a,b\",0.5,true\n2,c,0,false\n"
+        );
+
+        // batch, without header
+        let mut reader = CsvReaderBuilder::new(&schema)
+            .has_headers(false)
+            .build("1,a,1,true\n2,b,2,true\n3,c,3,true\n".as_bytes());
+        assert_eq!(reader.read_batch(2).unwrap().len(), 2);
+        assert_eq!(reader.read_batch(2).unwrap().len(), 1);
+        assert!(reader.read_batch(2).unwrap().is_empty());
+
+        // errors report the row and the column
+        let error = |input: &str| {
+            CsvReaderBuilder::new(&schema)
+                .null_value("NULL", NullHandling::Error)
+                .build(input.as_bytes())
+                .collect::<Result<Vec<_>, _>>()
+                .unwrap_err()
+                .to_string()
+        };
+        assert_eq!(
+            error("id,name,score,ok\n1,a,1,true\n2,b,x,true\n"),
+            "row 3, column 3 (field `score`): cannot parse \"x\" as F64"
+        );
+        assert_eq!(
+            error("id,name,score,ok\n1,a,NULL,true\n"),
+            "row 2, column 3 (field `score`): null value"
+        );
+        assert_eq!(
+            error("id,name,score,ok\n1,a,1\n"),
+            "row 2: 3 columns, expect 4"
+        );
+        assert_eq!(error("id,name,score,x\n"), "column 4: unknown field `x`");
+
+        // the header error ends the iteration, the row error skips the row
+        let mut reader =
+            CsvReaderBuilder::new(&schema).build("id,name,score,x\n1,a,1,true\n".as_bytes());
+        assert!(reader.next().unwrap().is_err());
+        assert!(reader.next().is_none());
+        let rows: Vec<_> = CsvReaderBuilder::new(&schema)
+            .build("id,name,score,ok\n1,a,x,true\n2,b,2,true\n".as_bytes())
+            .collect();
+        assert_eq!(rows.len(), 2);
+        assert!(rows[0].is_err());
+        assert!(rows[1].is_ok());
+    }
+}
@@ -0,0 +1,184 @@
+use std::convert::{TryFrom, TryInto};
+use std::fmt;
+
+use crate::encoding::read_lenenc_int;
+use crate::schema::SchemaRef;
+use crate::types;
+
+const HEX_LINE_BYTES: usize = 16;
+
+/// pretty print a record by the schema, create by `Buffer::display`.
+/// decode the bytes without the position index, so a corrupt record is printed up to the
+/// first broken field.
+#[derive(Clone, Copy)]
+pub struct BufferDisplay<'a, 'b> {
+    buf: &'a [u8],
+    schema: SchemaRef<'b>,
+    max_bytes: usize,
+    hex: bool,
+}
+
+impl<'a, 'b> BufferDisplay<'a, 'b> {
+    pub(crate) fn new(buf: &'a [u8], schema: SchemaRef<'b>) -> Self {
+        BufferDisplay {
+            buf,
+            schema,
+            max_bytes: 32,
+            hex: false,
+        }
+    }
+
+    /// `BYTES` values longer than `max_bytes` are truncated, default 32.
+    pub fn max_bytes(mut self, max_bytes: usize) -> Self {
+        self.max_bytes = max_bytes;
+        self
+    }
+
+    /// annotated hex dump, show the field boundaries and the length prefixes.
+    pub fn hex(mut self) -> Self {
+        self.hex = true;
+        self
+    }
+
+    fn fmt_field_name(&self, f: &mut fmt::Formatter<'_>, index: usize) -> fmt::Result {
+        let data_type = self.schema.data_types()[index];
+        match self
+            .schema
+            .schema()
+            .and_then(|schema| schema.field_name(index))
+        {
+            Some(name) => write!(f, "[{}] {}: {}", index, name, types::name(data_type)),
+            None => write!(f, "[{}] {}", index, types::name(data_type)),
+        }
+    }
+
+    fn fmt_value(&self, f: &mut fmt::Formatter<'_>, data_type: u8, data: &[u8]) -> fmt::Result {
+        // `data` has the exact length of the fixed width types
+        match data_type {
+            types::BOOL => write!(f, "{}", data[0] != 0),
+            types::I8 => write!(f, "{}", data[0] as i8),
+            types::U8 => write!(f, "{}", data[0]),
+            types::I16 => write!(f, "{}", i16::from_le_bytes(data.try_into().unwrap())),
+            types::U16 => write!(f, "{}", u16::from_le_bytes(data.try_into().unwrap())),
+            types::I32 => write!(f, "{}", i32::from_le_bytes(data.try_into().unwrap())),
+            types::U32 => write!(f, "{}", u32::from_le_bytes(data.try_into().unwrap())),
+            types::I64 => write!(f, "{}", i64::from_le_bytes(data.try_into().unwrap())),
+            types::U64 => write!(f, "{}", u64::from_le_bytes(data.try_into().unwrap())),
+            types::F32 => write!(f, "{}", f32::from_le_bytes(data.try_into().unwrap())),
+            types::F64 => write!(f, "{}", f64::from_le_bytes(data.try_into().unwrap())),
+            _ => {
+                let shown = &data[..data.len().min(self.max_bytes)];
+                match std::str::from_utf8(shown) {
+                    Ok(s) => write!(f, "{:?}", s)?,
+                    Err(_) => {
+                        write!(f, "[")?;
+                        fmt_hex(f, shown)?;
+                        write!(f, "]")?;
+                    }
+                }
+                if shown.len() < data.len() {
+                    write!(f, "... ({} bytes)", data.len())?;
+                }
+                Ok(())
+            }
+        }
+    }
+
+    fn fmt_hex_line(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+        offset: usize,
+        label: &str,
+        data: &[u8],
+    ) -> fmt::Result {
+        for (i, chunk) in data.chunks(HEX_LINE_BYTES).enumerate() {
+            let label = if i == 0 { label } else { "" };
+            write!(f, "{:08x}  {:<6}", offset + i * HEX_LINE_BYTES, label)?;
+            fmt_hex(f, chunk)?;
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+impl<'a, 'b> fmt::Display for BufferDisplay<'a, 'b> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut pos = 0;
+        for (index, data_type) in self.schema.data_types().iter().enumerate() {
+            let data_type = *data_type;
+            self.fmt_field_name(f, index)?;
+
+            let (prefix_len, data_len) = if data_type == types::BYTES {
+                match read_lenenc_int(self.buf, pos) {
+                    Ok((len, prefix_len)) => (prefix_len, len),
+                    Err(_) => {
+                        writeln!(f, " <broken length prefix at {}>", pos)?;
+                        break;
+                    }
+                }
+            } else {
+                (0, types::len(data_type) as u64)
+            };
+
+            let end = match usize::try_from(data_len)
+                .ok()
+                .and_then(|len| (pos + prefix_len).checked_add(len))
+            {
+                Some(end) => end,
+                None => {
+                    writeln!(f, " <invalid length {} at {}>", data_len, pos)?;
+                    break;
+                }
+            };
+            if end > self.buf.len() {
+                writeln!(
+                    f,
+                    " <{} bytes at {} out of the {} bytes buffer>",
+                    data_len,
+                    pos + prefix_len,
+                    self.buf.len()
+                )?;
+                break;
+            }
+
+            let data = &self.buf[pos + prefix_len..end];
+            if self.hex {
+                writeln!(f)?;
+                if prefix_len > 0 {
+                    self.fmt_hex_line(f, pos, "len", &self.buf[pos..pos + prefix_len])?;
+                }
+                self.fmt_hex_line(f, pos + prefix_len, "data", data)?;
+            } else {
+                write!(f, " = ")?;
+                self.fmt_value(f, data_type, data)?;
+                writeln!(f)?;
+            }
+            pos = end;
+        }
+
+        if pos < self.buf.len() {
+            writeln!(f, "<{} bytes not decoded>", self.buf.len() - pos)?;
+            if self.hex {
+                self.fmt_hex_line(f, pos, "", &self.buf[pos..])?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl<'a, 'b> fmt::Debug for BufferDisplay<'a, 'b> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+fn fmt_hex(f: &mut fmt::Formatter<'_>, data: &[u8]) -> fmt::Result {
+    for (i, b) in data.iter().enumerate() {
+        if i > 0 {
+            write!(f, " ")?;
+        }
+        write!(f, "{:02x}", b)?;
+    }
+    Ok(())
+}
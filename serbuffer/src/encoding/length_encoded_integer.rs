@@ -2,9 +2,24 @@
 
 use std::io;
 
-use bytes::{BufMut, BytesMut};
+use bytes::BufMut;
 
-pub fn write_lenenc_int(x: u64, buf: &mut BytesMut) -> usize {
+/// the encoded length of `x`, the same as returned by `write_lenenc_int`.
+#[inline]
+pub fn lenenc_int_len(x: u64) -> usize {
+    if x < 251 {
+        1
+    } else if x < 65_536 {
+        3
+    } else if x < 16_777_216 {
+        4
+    } else {
+        9
+    }
+}
+
+/// write `x` to any `BufMut`, return the encoded length.
+pub fn write_lenenc_int<B: BufMut + ?Sized>(x: u64, buf: &mut B) -> usize {
     if x < 251 {
         buf.put_u8(x as u8);
         1
@@ -15,7 +30,7 @@ pub fn write_lenenc_int(x: u64, buf: &mut BytesMut) -> usize {
     } else if x < 16_777_216 {
         buf.put_u8(0xFD);
         let n: [u8; 4] = (x as u32).to_le_bytes();
-        buf.put(&n[0..3]);
+        buf.put_slice(&n[0..3]);
         4
     } else {
         buf.put_u8(0xFE);
@@ -24,19 +39,28 @@ pub fn write_lenenc_int(x: u64, buf: &mut BytesMut) -> usize {
     }
 }
 
-pub fn read_lenenc_int(buf: &BytesMut, offset: usize) -> io::Result<(u64, usize)> {
-    let flag = &buf[offset];
+/// read the integer at `offset`, return the value and its encoded length.
+pub fn read_lenenc_int<B: AsRef<[u8]> + ?Sized>(
+    buf: &B,
+    offset: usize,
+) -> io::Result<(u64, usize)> {
+    let buf = buf.as_ref();
+    let flag = buf
+        .get(offset)
+        .ok_or_else(|| io::Error::from(io::ErrorKind::UnexpectedEof))?;
     match *flag {
         x if x < 0xFC => Ok((x as u64, 1)),
         0xFC => {
             let v = buf
                 .get(offset + 1..offset + 3)
                 .map(|x| unsafe { u16::from_le_bytes(*(x as *const _ as *const [_; 2])) })
-                .unwrap();
+                .ok_or_else(|| io::Error::from(io::ErrorKind::UnexpectedEof))?;
             Ok((v as u64, 3))
         }
         0xFD => {
-            let b = buf.get(offset + 1..offset + 4).unwrap();
+            let b = buf
+                .get(offset + 1..offset + 4)
+                .ok_or_else(|| io::Error::from(io::ErrorKind::UnexpectedEof))?;
             let le_4_bytes = [b[0], b[1], b[2], 0u8];
             Ok((u32::from_le_bytes(le_4_bytes) as u64, 4))
         }
@@ -44,13 +68,10 @@ pub fn read_lenenc_int(buf: &BytesMut, offset: usize) -> io::Result<(u64, usize)
             let v = buf
                 .get(offset + 1..offset + 9)
                 .map(|x| unsafe { u64::from_le_bytes(*(x as *const _ as *const [_; 8])) })
-                .unwrap();
+                .ok_or_else(|| io::Error::from(io::ErrorKind::UnexpectedEof))?;
             Ok((v, 9))
         }
-        0xFF => Err(io::Error::new(
-            io::ErrorKind::Other,
-            "Invalid length-encoded integer value",
-        )),
+        0xFF => Err(io::Error::other("Invalid length-encoded integer value")),
         _ => unreachable!(),
     }
 }
@@ -59,9 +80,9 @@ pub fn read_lenenc_int(buf: &BytesMut, offset: usize) -> io::Result<(u64, usize)
 mod tests {
     use bytes::BytesMut;
 
-    use std::borrow::BorrowMut;
-
-    use crate::encoding::length_encoded_integer::{read_lenenc_int, write_lenenc_int};
+    use crate::encoding::length_encoded_integer::{
+        lenenc_int_len, read_lenenc_int, write_lenenc_int,
+    };
 
     #[test]
     pub fn lenenc_int_0_test() {
@@ -71,22 +92,22 @@ mod tests {
         // write
         ////////////////////////////////////////////////////////////////////////////////////////////
 
-        let len = write_lenenc_int(0, bs.borrow_mut());
+        let len = write_lenenc_int(0, &mut bs);
         assert_eq!(len, 1);
-        let len = write_lenenc_int(251 - 1, bs.borrow_mut());
+        let len = write_lenenc_int(251 - 1, &mut bs);
         assert_eq!(len, 1);
 
-        let len = write_lenenc_int(251, bs.borrow_mut());
+        let len = write_lenenc_int(251, &mut bs);
         assert_eq!(len, 3);
-        let len = write_lenenc_int(65_536 - 1, bs.borrow_mut());
+        let len = write_lenenc_int(65_536 - 1, &mut bs);
         assert_eq!(len, 3);
 
-        let len = write_lenenc_int(65_536, bs.borrow_mut());
+        let len = write_lenenc_int(65_536, &mut bs);
         assert_eq!(len, 4);
-        let len = write_lenenc_int(16_777_216 - 1, bs.borrow_mut());
+        let len = write_lenenc_int(16_777_216 - 1, &mut bs);
         assert_eq!(len, 4);
 
-        let len = write_lenenc_int(16_777_216, bs.borrow_mut());
+        let len = write_lenenc_int(16_777_216, &mut bs);
         assert_eq!(len, 9);
 
         ////////////////////////////////////////////////////////////////////////////////////////////
@@ -117,5 +138,28 @@ mod tests {
         offset += v_read.1;
         let v_read = read_lenenc_int(&bs, offset).unwrap();
         assert_eq!(v_read.0, 16_777_216);
+
+        // other storages
+        let frozen = bs.clone().freeze();
+        assert_eq!(read_lenenc_int(&frozen, 1).unwrap(), (250, 1));
+        assert_eq!(read_lenenc_int(&bs.to_vec(), 2).unwrap(), (251, 3));
+        assert!(read_lenenc_int(&bs[..offset + 3], offset).is_err());
+
+        for x in [
+            0,
+            250,
+            251,
+            65_535,
+            65_536,
+            16_777_215,
+            16_777_216,
+            u64::MAX,
+        ] {
+            assert_eq!(lenenc_int_len(x), write_lenenc_int(x, &mut BytesMut::new()));
+        }
+
+        let mut vec = Vec::new();
+        assert_eq!(write_lenenc_int(65_536, &mut vec), 4);
+        assert_eq!(read_lenenc_int(&vec, 0).unwrap(), (65_536, 4));
     }
 }
@@ -1,3 +1,4 @@
 pub mod length_encoded_integer;
+pub use length_encoded_integer::lenenc_int_len;
 pub use length_encoded_integer::read_lenenc_int;
 pub use length_encoded_integer::write_lenenc_int;
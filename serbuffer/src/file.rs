@@ -0,0 +1,632 @@
+//! record file format, for the replay and the checkpoint files.
+//!
+//! file    := header block* footer trailer
+//! header  := magic "SBUF" | version u8 | compression u8 | schema_len u32 | schema text
+//! block   := 0x01 | payload_len u32 | record_count u32 | crc32c u32 | payload
+//!          | 0x02 | payload_len u32 | record_count u32 | crc32c u32 | raw_len u32 | compressed
+//! payload := (lenenc record_len | record)*
+//! footer  := 0xFF | block_count u32 | (block_offset u64 | record_count u32)* | crc32c u32
+//! trailer := footer_offset u64 | magic "SBUF"
+//!
+//! the integers are little endian, the schema text is the `Schema` text format.
+//! the checksum of a block is computed on the other header fields and the payload, the
+//! compressed payload for a compressed block. the block is stored uncompressed if the
+//! compression does not save space, or the ratio is beyond the limit of the reader.
+//! the checksum of the footer covers the block count and the block index.
+//! a file without footer (the writer is not finished) can still be read sequentially.
+
+use std::convert::{TryFrom, TryInto};
+use std::io::{ErrorKind, Read, Seek, SeekFrom, Write};
+
+use bytes::{BufMut, BytesMut};
+
+use crate::checksum::{crc32c, crc32c_append};
+use crate::compression::{Compression, MAX_RATIO};
+use crate::encoding::{read_lenenc_int, write_lenenc_int};
+use crate::{Buffer, Schema};
+
+pub const MAGIC: [u8; 4] = *b"SBUF";
+pub const VERSION: u8 = 1;
+
+pub(crate) const BLOCK_TAG: u8 = 0x01;
+pub(crate) const COMPRESSED_BLOCK_TAG: u8 = 0x02;
+pub(crate) const FOOTER_TAG: u8 = 0xFF;
+pub(crate) const BLOCK_HEADER_LEN: usize = 13;
+const COMPRESSED_BLOCK_HEADER_LEN: usize = 17;
+const TRAILER_LEN: usize = 12;
+/// tag, block count and checksum
+const FOOTER_MIN_LEN: usize = 9;
+
+/// the location of a block, from the footer.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BlockIndex {
+    /// offset of the block in the file
+    pub offset: u64,
+    /// index of the first record of the block in the file
+    pub first_record: u64,
+    pub record_count: u32,
+}
+
+/// build a `FileWriter`
+/// eg: FileWriterBuilder::new(&schema).block_size(1024 * 1024).build(file)
+pub struct FileWriterBuilder<'a> {
+    schema: &'a Schema,
+    block_size: usize,
+    compression: Compression,
+}
+
+impl<'a> FileWriterBuilder<'a> {
+    pub fn new(schema: &'a Schema) -> Self {
+        FileWriterBuilder {
+            schema,
+            block_size: 64 * 1024,
+            compression: Compression::None,
+        }
+    }
+
+    /// compress the blocks, default `Compression::None`.
+    pub fn compression(&mut self, compression: Compression) -> &mut Self {
+        self.compression = compression;
+        self
+    }
+
+    /// the block is written when its payload reach `block_size`, default 64 KiB.
+    pub fn block_size(&mut self, block_size: usize) -> &mut Self {
+        self.block_size = block_size;
+        self
+    }
+
+    /// write the file header.
+    pub fn build<W: Write>(&self, mut writer: W) -> Result<FileWriter<W>, std::io::Error> {
+        self.compression.check_enabled()?;
+        let schema = self.schema.to_string();
+
+        let mut header = Vec::with_capacity(10 + schema.len());
+        header.extend_from_slice(&MAGIC);
+        header.push(VERSION);
+        header.push(self.compression.id());
+        header.extend_from_slice(&(schema.len() as u32).to_le_bytes());
+        header.extend_from_slice(schema.as_bytes());
+        writer.write_all(header.as_slice())?;
+
+        Ok(FileWriter {
+            writer,
+            block_size: self.block_size,
+            compression: self.compression,
+            block: BytesMut::with_capacity(self.block_size),
+            block_records: 0,
+            offset: header.len() as u64,
+            blocks: Vec::new(),
+        })
+    }
+}
+
+/// write `Buffer`s to a record file, `finish` must be called to write the footer.
+pub struct FileWriter<W: Write> {
+    writer: W,
+    block_size: usize,
+    compression: Compression,
+    block: BytesMut,
+    block_records: u32,
+    /// the offset of the next block
+    offset: u64,
+    /// offset and record count of the written blocks
+    blocks: Vec<(u64, u32)>,
+}
+
+impl<W: Write> FileWriter<W> {
+    pub fn write(&mut self, buffer: &Buffer) -> Result<(), std::io::Error> {
+        write_lenenc_int(buffer.len() as u64, &mut self.block);
+        self.block.extend_from_slice(buffer.as_slice());
+        self.block_records += 1;
+
+        if self.block.len() >= self.block_size {
+            self.write_block()?;
+        }
+
+        Ok(())
+    }
+
+    /// write the pending records as a block, the block is smaller than `block_size`.
+    pub fn flush(&mut self) -> Result<(), std::io::Error> {
+        self.write_block()?;
+        self.writer.flush()
+    }
+
+    /// write the pending records and the footer, return the underlying writer.
+    pub fn finish(mut self) -> Result<W, std::io::Error> {
+        self.write_block()?;
+
+        let mut footer = Vec::with_capacity(9 + self.blocks.len() * 12 + TRAILER_LEN);
+        footer.push(FOOTER_TAG);
+        footer.extend_from_slice(&(self.blocks.len() as u32).to_le_bytes());
+        for (offset, record_count) in &self.blocks {
+            footer.extend_from_slice(&offset.to_le_bytes());
+            footer.extend_from_slice(&record_count.to_le_bytes());
+        }
+        let checksum = crc32c(&footer[1..]);
+        footer.extend_from_slice(&checksum.to_le_bytes());
+        footer.extend_from_slice(&self.offset.to_le_bytes());
+        footer.extend_from_slice(&MAGIC);
+        self.writer.write_all(footer.as_slice())?;
+        self.writer.flush()?;
+
+        Ok(self.writer)
+    }
+
+    fn write_block(&mut self) -> Result<(), std::io::Error> {
+        if self.block_records == 0 {
+            return Ok(());
+        }
+
+        let raw_len: u32 = self.block.len().try_into().map_err(|_| {
+            std::io::Error::new(ErrorKind::InvalidInput, "the block is larger than 4 GiB")
+        })?;
+
+        let compressed = match self.compression {
+            Compression::None => None,
+            compression => Some(compression.compress(self.block.as_ref())?).filter(|compressed| {
+                compressed.len() < self.block.len()
+                    && self.block.len() <= compressed.len() * MAX_RATIO
+            }),
+        };
+
+        let mut header = [0u8; COMPRESSED_BLOCK_HEADER_LEN];
+        let (header, payload) = match &compressed {
+            Some(compressed) => {
+                header[0] = COMPRESSED_BLOCK_TAG;
+                header[13..17].copy_from_slice(&raw_len.to_le_bytes());
+                (&mut header[..], compressed.as_slice())
+            }
+            None => {
+                header[0] = BLOCK_TAG;
+                (&mut header[..BLOCK_HEADER_LEN], self.block.as_ref())
+            }
+        };
+        header[1..5].copy_from_slice(&(payload.len() as u32).to_le_bytes());
+        header[5..9].copy_from_slice(&self.block_records.to_le_bytes());
+        let checksum = block_checksum(header, payload);
+        header[9..13].copy_from_slice(&checksum.to_le_bytes());
+        self.writer.write_all(header)?;
+        self.writer.write_all(payload)?;
+
+        self.blocks.push((self.offset, self.block_records));
+        self.offset += (header.len() + payload.len()) as u64;
+        self.block.clear();
+        self.block_records = 0;
+
+        Ok(())
+    }
+}
+
+/// read the `Buffer`s of a record file, the records share the memory of their block.
+pub struct FileReader<R: Read> {
+    reader: R,
+    schema: Schema,
+    compression: Compression,
+    /// the unread records of the current block
+    block: BytesMut,
+    block_records: u32,
+    /// the offset of the next block
+    offset: u64,
+    /// the footer is reached
+    finished: bool,
+    index: Option<Vec<BlockIndex>>,
+}
+
+/// the checksum of the block, `header` is the whole block header include the tag.
+pub(crate) fn block_checksum(header: &[u8], payload: &[u8]) -> u32 {
+    let crc = crc32c(&header[1..9]);
+    let crc = crc32c_append(crc, &header[13..]);
+    crc32c_append(crc, payload)
+}
+
+/// read exactly `len` bytes, the buffer grows with the read bytes instead of trusting `len`.
+fn read_bounded<R: Read>(reader: R, len: usize) -> Result<BytesMut, std::io::Error> {
+    let mut writer = BytesMut::new().writer();
+    let read = std::io::copy(&mut reader.take(len as u64), &mut writer)?;
+    if read < len as u64 {
+        return Err(std::io::Error::from(ErrorKind::UnexpectedEof));
+    }
+
+    Ok(writer.into_inner())
+}
+
+/// read the file header, return the schema, the compression and the header length.
+pub(crate) fn read_header<R: Read>(
+    mut reader: R,
+) -> Result<(Schema, Compression, usize), std::io::Error> {
+    let mut header = [0u8; 10];
+    reader.read_exact(&mut header)?;
+    if header[0..4] != MAGIC {
+        return Err(std::io::Error::new(
+            ErrorKind::InvalidData,
+            "not a serbuffer record file",
+        ));
+    }
+    if header[4] != VERSION {
+        return Err(std::io::Error::new(
+            ErrorKind::InvalidData,
+            format!("unsupported record file version {}", header[4]),
+        ));
+    }
+
+    let compression = Compression::from_id(header[5])?;
+    let schema_len = u32::from_le_bytes(header[6..10].try_into().unwrap()) as usize;
+    let schema = read_bounded(&mut reader, schema_len)?;
+    let schema = std::str::from_utf8(schema.as_ref())
+        .map_err(|e| std::io::Error::new(ErrorKind::InvalidData, e))?
+        .parse()?;
+
+    Ok((schema, compression, header.len() + schema_len))
+}
+
+impl<R: Read> FileReader<R> {
+    /// read the file header.
+    pub fn new(mut reader: R) -> Result<Self, std::io::Error> {
+        let (schema, compression, header_len) = read_header(&mut reader)?;
+
+        Ok(FileReader {
+            reader,
+            schema,
+            compression,
+            block: BytesMut::new(),
+            block_records: 0,
+            offset: header_len as u64,
+            finished: false,
+            index: None,
+        })
+    }
+
+    /// the schema of the records.
+    pub fn schema(&self) -> &Schema {
+        &self.schema
+    }
+
+    /// the block compression of the file.
+    pub fn compression(&self) -> Compression {
+        self.compression
+    }
+
+    /// read the next record, `None` at the end of the file.
+    pub fn read(&mut self) -> Result<Option<Buffer>, std::io::Error> {
+        while self.block_records == 0 {
+            if self.finished || !self.read_block()? {
+                return Ok(None);
+            }
+        }
+
+        let (len, prefix_len) = read_lenenc_int(self.block.as_ref(), 0)?;
+        let len = usize::try_from(len)
+            .ok()
+            .filter(|len| {
+                prefix_len
+                    .checked_add(*len)
+                    .is_some_and(|end| end <= self.block.len())
+            })
+            .ok_or_else(|| {
+                std::io::Error::new(ErrorKind::InvalidData, "the record is out of the block")
+            })?;
+
+        let _ = self.block.split_to(prefix_len);
+        self.block_records -= 1;
+        Ok(Some(Buffer::from(self.block.split_to(len))))
+    }
+
+    /// read the next block, return `false` at the footer or the end of an unfinished file.
+    fn read_block(&mut self) -> Result<bool, std::io::Error> {
+        let mut header = [0u8; COMPRESSED_BLOCK_HEADER_LEN];
+        match self.reader.read(&mut header[..1])? {
+            0 => {
+                self.finished = true;
+                return Ok(false);
+            }
+            _ if header[0] == FOOTER_TAG => {
+                self.finished = true;
+                return Ok(false);
+            }
+            _ if header[0] != BLOCK_TAG && header[0] != COMPRESSED_BLOCK_TAG => {
+                return Err(std::io::Error::new(
+                    ErrorKind::InvalidData,
+                    format!("broken block header at {}", self.offset),
+                ));
+            }
+            _ => {}
+        }
+        let header_len = if header[0] == COMPRESSED_BLOCK_TAG {
+            COMPRESSED_BLOCK_HEADER_LEN
+        } else {
+            BLOCK_HEADER_LEN
+        };
+        self.reader.read_exact(&mut header[1..header_len])?;
+
+        let payload_len = u32::from_le_bytes(header[1..5].try_into().unwrap()) as usize;
+        let record_count = u32::from_le_bytes(header[5..9].try_into().unwrap());
+        let checksum = u32::from_le_bytes(header[9..13].try_into().unwrap());
+
+        let mut block = read_bounded(&mut self.reader, payload_len)?;
+        if block_checksum(&header[..header_len], block.as_ref()) != checksum {
+            return Err(std::io::Error::new(
+                ErrorKind::InvalidData,
+                format!("checksum mismatch of the block at {}", self.offset),
+            ));
+        }
+
+        if header[0] == COMPRESSED_BLOCK_TAG {
+            let raw_len = u32::from_le_bytes(header[13..17].try_into().unwrap()) as usize;
+            block = self.compression.decompress(block.as_ref(), raw_len)?;
+        }
+
+        self.block = block;
+        self.block_records = record_count;
+        self.offset += (header_len + payload_len) as u64;
+        Ok(true)
+    }
+}
+
+impl<R: Read + Seek> FileReader<R> {
+    /// the block index from the footer, fail if the file is not finished.
+    pub fn index(&mut self) -> Result<&[BlockIndex], std::io::Error> {
+        if self.index.is_none() {
+            let position = self.reader.stream_position()?;
+            let index = self.read_index();
+            self.reader.seek(SeekFrom::Start(position))?;
+            self.index = Some(index?);
+        }
+
+        Ok(self.index.as_deref().unwrap())
+    }
+
+    /// the number of records, from the footer.
+    pub fn record_count(&mut self) -> Result<u64, std::io::Error> {
+        Ok(self
+            .index()?
+            .last()
+            .map(|block| block.first_record + block.record_count as u64)
+            .unwrap_or(0))
+    }
+
+    /// the next `read` return the record `n`, or `None` if `n` is out of the file.
+    pub fn seek_record(&mut self, n: u64) -> Result<(), std::io::Error> {
+        let index = self.index()?;
+        let block = match index
+            .iter()
+            .find(|block| n < block.first_record + block.record_count as u64)
+        {
+            Some(block) => *block,
+            None => {
+                self.block.clear();
+                self.block_records = 0;
+                self.finished = true;
+                return Ok(());
+            }
+        };
+
+        self.reader.seek(SeekFrom::Start(block.offset))?;
+        self.offset = block.offset;
+        self.finished = false;
+        self.block_records = 0;
+        if !self.read_block()? {
+            return Err(std::io::Error::new(
+                ErrorKind::InvalidData,
+                format!("no block at {}", block.offset),
+            ));
+        }
+
+        for _ in block.first_record..n {
+            self.read()?;
+        }
+        Ok(())
+    }
+
+    fn read_index(&mut self) -> Result<Vec<BlockIndex>, std::io::Error> {
+        let mut trailer = [0u8; TRAILER_LEN];
+        self.reader.seek(SeekFrom::End(-(TRAILER_LEN as i64)))?;
+        self.reader.read_exact(&mut trailer)?;
+        if trailer[8..] != MAGIC {
+            return Err(std::io::Error::new(
+                ErrorKind::InvalidData,
+                "no footer, the record file is not finished",
+            ));
+        }
+
+        let footer_offset = u64::from_le_bytes(trailer[..8].try_into().unwrap());
+        let trailer_offset = self.reader.seek(SeekFrom::End(-(TRAILER_LEN as i64)))?;
+        // the footer is between its offset and the trailer, check it before reading
+        let footer_len = trailer_offset
+            .checked_sub(footer_offset)
+            .filter(|len| *len >= FOOTER_MIN_LEN as u64)
+            .ok_or_else(|| {
+                std::io::Error::new(
+                    ErrorKind::InvalidData,
+                    format!("broken footer offset {}", footer_offset),
+                )
+            })? as usize;
+
+        self.reader.seek(SeekFrom::Start(footer_offset))?;
+        let footer = read_bounded(&mut self.reader, footer_len)?;
+        let (footer, checksum) = footer.split_at(footer_len - 4);
+        if crc32c(&footer[1..]) != u32::from_le_bytes(checksum.try_into().unwrap()) {
+            return Err(std::io::Error::new(
+                ErrorKind::InvalidData,
+                "checksum mismatch of the footer",
+            ));
+        }
+
+        let block_count = u32::from_le_bytes(footer[1..5].try_into().unwrap()) as usize;
+        let entries = &footer[5..];
+        if footer[0] != FOOTER_TAG || entries.len() != block_count * 12 {
+            return Err(std::io::Error::new(
+                ErrorKind::InvalidData,
+                format!("broken footer at {}", footer_offset),
+            ));
+        }
+
+        let mut first_record = 0;
+        let index = entries
+            .chunks(12)
+            .map(|entry| {
+                let block = BlockIndex {
+                    offset: u64::from_le_bytes(entry[..8].try_into().unwrap()),
+                    first_record,
+                    record_count: u32::from_le_bytes(entry[8..].try_into().unwrap()),
+                };
+                first_record += block.record_count as u64;
+                block
+            })
+            .collect();
+
+        Ok(index)
+    }
+}
+
+impl<R: Read> Iterator for FileReader<R> {
+    type Item = Result<Buffer, std::io::Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read().transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::TryInto;
+    use std::io::Cursor;
+
+    use crate::compression::{Compression, MAX_RATIO};
+    use crate::file::{block_checksum, FileReader, FileWriterBuilder, BLOCK_HEADER_LEN, BLOCK_TAG};
+    use crate::test_util::{record, records, schema, write_file};
+    use crate::Buffer;
+
+    #[test]
+    pub fn file_test() {
+        let schema = schema();
+        let file = write_file(&schema, 20, Vec::new());
+
+        let reader = FileReader::new(Cursor::new(file.as_slice())).unwrap();
+        assert_eq!(reader.schema(), &schema);
+        let buffers: Vec<Buffer> = reader.collect::<Result<_, _>>().unwrap();
+        assert_eq!(buffers, records(&schema, 0..20));
+
+        // seek by the footer index
+        let mut reader = FileReader::new(Cursor::new(file.as_slice())).unwrap();
+        assert!(reader.index().unwrap().len() > 1);
+        assert_eq!(reader.record_count().unwrap(), 20);
+        reader.seek_record(13).unwrap();
+        assert_eq!(reader.read().unwrap().unwrap(), record(&schema, 13));
+        assert_eq!(reader.count(), 6);
+
+        let mut reader = FileReader::new(Cursor::new(file.as_slice())).unwrap();
+        reader.seek_record(20).unwrap();
+        assert!(reader.read().unwrap().is_none());
+
+        // corrupt block
+        let mut corrupt = file.clone();
+        let len = corrupt.len();
+        corrupt[len / 2] ^= 0x01;
+        let reader = FileReader::new(Cursor::new(corrupt.as_slice())).unwrap();
+        assert!(reader.collect::<Result<Vec<_>, _>>().is_err());
+
+        // corrupt block header, the record count and a huge payload length
+        let block_offset = FileReader::new(Cursor::new(file.as_slice()))
+            .unwrap()
+            .offset as usize;
+        for (field, value) in [(5, 21u32), (1, u32::MAX)] {
+            let mut corrupt = file.clone();
+            corrupt[block_offset + field..block_offset + field + 4]
+                .copy_from_slice(&value.to_le_bytes());
+            let reader = FileReader::new(Cursor::new(corrupt.as_slice())).unwrap();
+            assert!(reader.collect::<Result<Vec<_>, _>>().is_err());
+        }
+
+        // corrupt block count of the footer
+        let len = file.len();
+        let footer_offset = u64::from_le_bytes(file[len - 12..len - 4].try_into().unwrap());
+        let mut corrupt = file.clone();
+        corrupt[footer_offset as usize + 4] = 0xFF;
+        let mut reader = FileReader::new(Cursor::new(corrupt.as_slice())).unwrap();
+        assert!(reader.index().is_err());
+
+        // crafted block with a valid checksum and an overflowing record length
+        let mut crafted = file[..block_offset].to_vec();
+        let payload = [0xFE, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF];
+        let mut header = [0u8; BLOCK_HEADER_LEN];
+        header[0] = BLOCK_TAG;
+        header[1..5].copy_from_slice(&(payload.len() as u32).to_le_bytes());
+        header[5..9].copy_from_slice(&1u32.to_le_bytes());
+        let checksum = block_checksum(&header, &payload);
+        header[9..13].copy_from_slice(&checksum.to_le_bytes());
+        crafted.extend_from_slice(&header);
+        crafted.extend_from_slice(&payload);
+        let mut reader = FileReader::new(Cursor::new(crafted.as_slice())).unwrap();
+        assert_eq!(
+            reader.read().unwrap_err().kind(),
+            std::io::ErrorKind::InvalidData
+        );
+
+        // unfinished file is read sequentially
+        let mut writer = FileWriterBuilder::new(&schema).build(Vec::new()).unwrap();
+        writer.write(&record(&schema, 1)).unwrap();
+        writer.flush().unwrap();
+        let file = std::mem::take(&mut writer.writer);
+        let mut reader = FileReader::new(Cursor::new(file.as_slice())).unwrap();
+        assert!(reader.index().is_err());
+        assert_eq!(reader.count(), 1);
+    }
+
+    #[test]
+    pub fn file_compression_test() {
+        let schema = schema();
+
+        for compression in [Compression::Lz4, Compression::Zstd(3), Compression::Snappy] {
+            if compression.check_enabled().is_err() {
+                assert!(FileWriterBuilder::new(&schema)
+                    .compression(compression)
+                    .build(Vec::new())
+                    .is_err());
+                continue;
+            }
+
+            let mut writer = FileWriterBuilder::new(&schema)
+                .block_size(1024)
+                .compression(compression)
+                .build(Vec::new())
+                .unwrap();
+            for buffer in records(&schema, 0..1000) {
+                writer.write(&buffer).unwrap();
+            }
+            let file = writer.finish().unwrap();
+            assert!(file.len() < 1000 * 10, "{:?}", compression);
+
+            let mut reader = FileReader::new(Cursor::new(file.as_slice())).unwrap();
+            assert_eq!(reader.compression().id(), compression.id());
+            reader.seek_record(500).unwrap();
+            assert_eq!(reader.read().unwrap().unwrap(), record(&schema, 500));
+
+            let reader = FileReader::new(Cursor::new(file.as_slice())).unwrap();
+            let buffers: Vec<Buffer> = reader.collect::<Result<_, _>>().unwrap();
+            assert_eq!(buffers.len(), 1000);
+            assert_eq!(buffers[999], record(&schema, 999));
+
+            // a corrupt raw length is rejected before the allocation
+            assert!(compression.decompress(&[0; 4], 4 * MAX_RATIO + 1).is_err());
+
+            // the blocks compressed beyond the max ratio are still readable
+            let mut zeros = Buffer::new();
+            {
+                let mut writer = zeros.as_writer(&schema);
+                writer.set_i64(0).unwrap();
+                writer.set_bytes(&vec![0; 1024 * 1024]).unwrap();
+                writer.finish().unwrap();
+            }
+            let mut writer = FileWriterBuilder::new(&schema)
+                .compression(compression)
+                .build(Vec::new())
+                .unwrap();
+            writer.write(&zeros).unwrap();
+            let file = writer.finish().unwrap();
+            let mut reader = FileReader::new(Cursor::new(file.as_slice())).unwrap();
+            assert_eq!(reader.read().unwrap().unwrap(), zeros);
+        }
+    }
+}
@@ -0,0 +1,222 @@
+//! blocking record streams over `std::io`, each record is prefixed by the length encoded
+//! integer of its length. the same framing as the `tokio` adaptors in `codec`.
+
+use std::io::{ErrorKind, Read, Write};
+
+use bytes::BytesMut;
+
+use crate::encoding::{read_lenenc_int, write_lenenc_int};
+use crate::Buffer;
+
+/// the default max record size of the readers, 64 MiB.
+pub const DEFAULT_MAX_RECORD_SIZE: usize = 64 * 1024 * 1024;
+
+const READ_SIZE: usize = 8 * 1024;
+
+/// split the next record from `src`, `None` if the record is not complete.
+pub(crate) fn decode_frame(
+    src: &mut BytesMut,
+    max_record_size: usize,
+) -> Result<Option<Buffer>, std::io::Error> {
+    let (len, prefix_len) = match read_lenenc_int(src.as_ref(), 0) {
+        Ok(v) => v,
+        Err(e) if e.kind() == ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e),
+    };
+
+    if len > max_record_size as u64 {
+        return Err(std::io::Error::new(
+            ErrorKind::InvalidData,
+            format!(
+                "record of {} bytes exceeds the max record size {}",
+                len, max_record_size
+            ),
+        ));
+    }
+
+    let frame_len = prefix_len + len as usize;
+    if src.len() < frame_len {
+        src.reserve(frame_len - src.len());
+        return Ok(None);
+    }
+
+    let _ = src.split_to(prefix_len);
+    Ok(Some(Buffer::from(src.split_to(len as usize))))
+}
+
+/// write the length prefixed `Buffer`s.
+/// every record is written by `write_all`, wrap the files and the sockets in a `BufWriter`.
+pub struct StreamWriter<W: Write> {
+    writer: W,
+    prefix: BytesMut,
+}
+
+impl<W: Write> StreamWriter<W> {
+    pub fn new(writer: W) -> Self {
+        StreamWriter {
+            writer,
+            prefix: BytesMut::with_capacity(9),
+        }
+    }
+
+    pub fn write(&mut self, buffer: &Buffer) -> Result<(), std::io::Error> {
+        self.prefix.clear();
+        write_lenenc_int(buffer.len() as u64, &mut self.prefix);
+        self.writer.write_all(self.prefix.as_ref())?;
+        self.writer.write_all(buffer.as_slice())
+    }
+
+    pub fn flush(&mut self) -> Result<(), std::io::Error> {
+        self.writer.flush()
+    }
+
+    pub fn get_ref(&self) -> &W {
+        &self.writer
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+/// read the length prefixed `Buffer`s.
+/// the data is read into a reused storage, the records are split from it without copy.
+pub struct StreamReader<R: Read> {
+    reader: R,
+    storage: BytesMut,
+    max_record_size: usize,
+    /// the stream offset of the next record
+    offset: u64,
+    /// stop after the first error
+    failed: bool,
+}
+
+impl<R: Read> StreamReader<R> {
+    pub fn new(reader: R) -> Self {
+        StreamReader::with_max_record_size(reader, DEFAULT_MAX_RECORD_SIZE)
+    }
+
+    /// the larger records are rejected before the allocation.
+    pub fn with_max_record_size(reader: R, max_record_size: usize) -> Self {
+        StreamReader {
+            reader,
+            storage: BytesMut::with_capacity(READ_SIZE),
+            max_record_size,
+            offset: 0,
+            failed: false,
+        }
+    }
+
+    /// the stream offset of the next record.
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    /// read the next record, `None` at the end of the stream.
+    pub fn read(&mut self) -> Result<Option<Buffer>, std::io::Error> {
+        if self.failed {
+            return Ok(None);
+        }
+
+        let result = self.read_frame();
+        if result.is_err() {
+            self.failed = true;
+        }
+        result
+    }
+
+    fn read_frame(&mut self) -> Result<Option<Buffer>, std::io::Error> {
+        loop {
+            let len = self.storage.len();
+            if let Some(buffer) = decode_frame(&mut self.storage, self.max_record_size)? {
+                self.offset += (len - self.storage.len()) as u64;
+                return Ok(Some(buffer));
+            }
+
+            if self.fill()? == 0 {
+                if self.storage.is_empty() {
+                    return Ok(None);
+                }
+                return Err(std::io::Error::new(
+                    ErrorKind::UnexpectedEof,
+                    format!(
+                        "the record at {} is truncated to {} bytes",
+                        self.offset,
+                        self.storage.len()
+                    ),
+                ));
+            }
+        }
+    }
+
+    /// read more data to the storage, return the read size.
+    fn fill(&mut self) -> Result<usize, std::io::Error> {
+        if self.storage.capacity() - self.storage.len() < READ_SIZE / 2 {
+            self.storage.reserve(READ_SIZE);
+        }
+
+        // zero at most `READ_SIZE` per read, the storage of a large record is reserved at once
+        let len = self.storage.len();
+        let end = self.storage.capacity().min(len + READ_SIZE);
+        self.storage.resize(end, 0);
+        loop {
+            match self.reader.read(&mut self.storage[len..]) {
+                Ok(n) => {
+                    self.storage.truncate(len + n);
+                    return Ok(n);
+                }
+                Err(e) if e.kind() == ErrorKind::Interrupted => {}
+                Err(e) => {
+                    self.storage.truncate(len);
+                    return Err(e);
+                }
+            }
+        }
+    }
+}
+
+impl<R: Read> Iterator for StreamReader<R> {
+    type Item = Result<Buffer, std::io::Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read().transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::io::{StreamReader, StreamWriter};
+    use crate::test_util::{record_named, records, schema};
+    use crate::Buffer;
+
+    #[test]
+    pub fn stream_test() {
+        let schema = schema();
+        let mut expected = records(&schema, 0..100);
+        expected[50] = record_named(&schema, 50, "x".repeat(70_000).as_str());
+
+        let mut writer = StreamWriter::new(Vec::new());
+        for buffer in &expected {
+            writer.write(buffer).unwrap();
+        }
+        let data = writer.into_inner();
+
+        let mut reader = StreamReader::new(data.as_slice());
+        assert_eq!(reader.offset(), 0);
+        let first = reader.read().unwrap().unwrap();
+        assert_eq!(first, expected[0]);
+        assert_eq!(reader.offset(), 1 + first.len() as u64);
+
+        let buffers: Vec<Buffer> = reader.collect::<Result<_, _>>().unwrap();
+        assert_eq!(buffers, expected[1..]);
+
+        // truncated stream
+        let mut reader = StreamReader::new(&data[..data.len() - 1]);
+        assert_eq!(reader.by_ref().take_while(|r| r.is_ok()).count(), 99);
+        assert!(reader.next().is_none());
+
+        // max record size
+        let mut reader = StreamReader::with_max_record_size(data.as_slice(), 1024);
+        assert_eq!(reader.by_ref().take_while(|r| r.is_ok()).count(), 50);
+    }
+}
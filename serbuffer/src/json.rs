@@ -0,0 +1,291 @@
+//! convert records to and from JSON objects keyed by the field names of a named `Schema`.
+//! `BYTES` fields are strings, or `{"base64": "..."}` objects if they are not valid UTF-8.
+//! the types are checked strictly.
+
+use std::convert::TryFrom;
+use std::io::ErrorKind;
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use serde_json::{Map, Number};
+
+use crate::{types, Buffer, Schema, Value};
+
+/// the key of the base64 object of the non UTF-8 `BYTES`
+const BASE64_KEY: &str = "base64";
+
+/// convert the record to a JSON object.
+pub fn to_json(buffer: &Buffer, schema: &Schema) -> Result<serde_json::Value, std::io::Error> {
+    let field_names = field_names(schema)?;
+    buffer.validate(schema.data_types())?;
+
+    let reader = buffer.as_reader(schema);
+    let mut object = Map::with_capacity(field_names.len());
+    for (index, field_name) in field_names.iter().enumerate() {
+        let value = match reader.get(index)? {
+            Value::Bool(v) => serde_json::Value::Bool(v),
+            Value::I8(v) => serde_json::Value::from(v),
+            Value::U8(v) => serde_json::Value::from(v),
+            Value::I16(v) => serde_json::Value::from(v),
+            Value::U16(v) => serde_json::Value::from(v),
+            Value::I32(v) => serde_json::Value::from(v),
+            Value::U32(v) => serde_json::Value::from(v),
+            Value::I64(v) => serde_json::Value::from(v),
+            Value::U64(v) => serde_json::Value::from(v),
+            Value::F32(v) => f32_to_json(field_name, v)?,
+            Value::F64(v) => float_to_json(field_name, v)?,
+            Value::Bytes(v) => bytes_to_json(v),
+            Value::Str(v) => serde_json::Value::String(v.to_string()),
+        };
+        object.insert(field_name.clone(), value);
+    }
+
+    Ok(serde_json::Value::Object(object))
+}
+
+/// convert the record to a JSON object string.
+pub fn to_json_string(buffer: &Buffer, schema: &Schema) -> Result<String, std::io::Error> {
+    to_json(buffer, schema).map(|value| value.to_string())
+}
+
+/// parse a JSON object to a record, every field must be present and no unknown fields.
+pub fn from_json(value: &serde_json::Value, schema: &Schema) -> Result<Buffer, std::io::Error> {
+    let field_names = field_names(schema)?;
+    let object = value.as_object().ok_or_else(|| {
+        std::io::Error::new(
+            ErrorKind::InvalidData,
+            format!("expect a JSON object, found {}", value),
+        )
+    })?;
+
+    if let Some(field_name) = field_names.iter().find(|name| !object.contains_key(*name)) {
+        return Err(std::io::Error::new(
+            ErrorKind::InvalidData,
+            format!("field `{}` is missing", field_name),
+        ));
+    }
+
+    let mut buffer = Buffer::with_capacity(types::min_encoded_size(schema.data_types()));
+    {
+        // stage the fields, nothing is written to the buffer if any field fails
+        let mut writer = buffer.as_unordered_writer(schema);
+        for (field_name, value) in object {
+            let index = schema.index_of(field_name).ok_or_else(|| {
+                std::io::Error::new(
+                    ErrorKind::InvalidData,
+                    format!("unknown field `{}`", field_name),
+                )
+            })?;
+
+            let data_type = schema.data_types()[index];
+            match data_type {
+                types::BOOL => writer.set_bool(
+                    index,
+                    value
+                        .as_bool()
+                        .ok_or_else(|| type_error(field_name, data_type, value))?,
+                )?,
+                types::I8 => writer.set_i8(index, json_to_int(field_name, data_type, value)?)?,
+                types::U8 => writer.set_u8(index, json_to_int(field_name, data_type, value)?)?,
+                types::I16 => writer.set_i16(index, json_to_int(field_name, data_type, value)?)?,
+                types::U16 => writer.set_u16(index, json_to_int(field_name, data_type, value)?)?,
+                types::I32 => writer.set_i32(index, json_to_int(field_name, data_type, value)?)?,
+                types::U32 => writer.set_u32(index, json_to_int(field_name, data_type, value)?)?,
+                types::I64 => writer.set_i64(index, json_to_int(field_name, data_type, value)?)?,
+                types::U64 => writer.set_u64(index, json_to_int(field_name, data_type, value)?)?,
+                types::F32 => writer.set_f32(index, json_to_f32(field_name, value)?)?,
+                types::F64 => writer.set_f64(
+                    index,
+                    value
+                        .as_f64()
+                        .ok_or_else(|| type_error(field_name, data_type, value))?,
+                )?,
+                _ => {
+                    let bytes = json_to_bytes(value)
+                        .ok_or_else(|| type_error(field_name, data_type, value))?;
+                    writer.set_bytes(index, bytes.as_slice())?
+                }
+            }
+        }
+        writer.finish()?;
+    }
+
+    Ok(buffer)
+}
+
+/// parse a JSON object string to a record.
+pub fn from_json_str(s: &str, schema: &Schema) -> Result<Buffer, std::io::Error> {
+    let value: serde_json::Value = serde_json::from_str(s)?;
+    from_json(&value, schema)
+}
+
+fn field_names(schema: &Schema) -> Result<&[String], std::io::Error> {
+    schema.field_names().ok_or_else(|| {
+        std::io::Error::new(
+            ErrorKind::InvalidInput,
+            "the JSON conversion require a named schema",
+        )
+    })
+}
+
+fn float_to_json(field_name: &str, v: f64) -> Result<serde_json::Value, std::io::Error> {
+    Number::from_f64(v)
+        .map(serde_json::Value::Number)
+        .ok_or_else(|| {
+            std::io::Error::new(
+                ErrorKind::InvalidData,
+                format!("field `{}`: {} is not representable in JSON", field_name, v),
+            )
+        })
+}
+
+/// format from the `f32` value, the widened `f64` print the noise digits, eg: 0.10000000149011612
+fn f32_to_json(field_name: &str, v: f32) -> Result<serde_json::Value, std::io::Error> {
+    float_to_json(field_name, v.to_string().parse().unwrap())
+}
+
+fn json_to_f32(field_name: &str, value: &serde_json::Value) -> Result<f32, std::io::Error> {
+    value
+        .as_f64()
+        .filter(|v| v.abs() <= f32::MAX as f64)
+        .map(|v| v as f32)
+        .ok_or_else(|| type_error(field_name, types::F32, value))
+}
+
+fn bytes_to_json(v: &[u8]) -> serde_json::Value {
+    match std::str::from_utf8(v) {
+        Ok(s) => serde_json::Value::String(s.to_string()),
+        Err(_) => {
+            let mut object = Map::with_capacity(1);
+            object.insert(
+                BASE64_KEY.to_string(),
+                serde_json::Value::String(STANDARD.encode(v)),
+            );
+            serde_json::Value::Object(object)
+        }
+    }
+}
+
+fn json_to_bytes(value: &serde_json::Value) -> Option<Vec<u8>> {
+    match value {
+        serde_json::Value::String(s) => Some(s.as_bytes().to_vec()),
+        serde_json::Value::Object(object) if object.len() == 1 => object
+            .get(BASE64_KEY)
+            .and_then(|v| v.as_str())
+            .and_then(|s| STANDARD.decode(s).ok()),
+        _ => None,
+    }
+}
+
+fn json_to_int<T>(
+    field_name: &str,
+    data_type: u8,
+    value: &serde_json::Value,
+) -> Result<T, std::io::Error>
+where
+    T: TryFrom<i64> + TryFrom<u64>,
+{
+    let v = match value {
+        serde_json::Value::Number(n) => match (n.as_i64(), n.as_u64()) {
+            (Some(v), _) => T::try_from(v).ok(),
+            (None, Some(v)) => T::try_from(v).ok(),
+            _ => None,
+        },
+        _ => None,
+    };
+
+    v.ok_or_else(|| type_error(field_name, data_type, value))
+}
+
+fn type_error(field_name: &str, data_type: u8, value: &serde_json::Value) -> std::io::Error {
+    std::io::Error::new(
+        ErrorKind::InvalidData,
+        format!(
+            "field `{}`: {} does not fit {}",
+            field_name,
+            value,
+            types::name(data_type)
+        ),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::json::{from_json_str, to_json, to_json_string};
+    use crate::{types, Buffer, Schema};
+
+    #[test]
+    pub fn json_test() {
+        let schema = Schema::named(
+            &["id", "score", "ratio", "ok", "payload"],
+            &[types::I64, types::U8, types::F64, types::BOOL, types::BYTES],
+        )
+        .unwrap();
+
+        let mut buffer = Buffer::new();
+        {
+            let mut writer = buffer.as_writer(&schema);
+            writer.set_i64(-10).unwrap();
+            writer.set_u8(200).unwrap();
+            writer.set_f64(0.5).unwrap();
+            writer.set_bool(true).unwrap();
+            writer.set_bytes(&[0, 1, 255]).unwrap();
+            writer.finish().unwrap();
+        }
+
+        let json = to_json_string(&buffer, &schema).unwrap();
+        assert_eq!(
+            json,
+            r#"{"id":-10,"score":200,"ratio":0.5,"ok":true,"payload":{"base64":"AAH/"}}"#
+        );
+        assert_eq!(from_json_str(json.as_str(), &schema).unwrap(), buffer);
+
+        let error = |s: &str| from_json_str(s, &schema).unwrap_err().to_string();
+        assert_eq!(
+            error(r#"{"id":1,"score":256,"ratio":0.5,"ok":true,"payload":""}"#),
+            "field `score`: 256 does not fit U8"
+        );
+        assert_eq!(
+            error(r#"{"id":1.5,"score":1,"ratio":0.5,"ok":true,"payload":""}"#),
+            "field `id`: 1.5 does not fit I64"
+        );
+        assert_eq!(
+            error(r#"{"id":1,"score":1,"ratio":0.5,"ok":1,"payload":""}"#),
+            "field `ok`: 1 does not fit BOOL"
+        );
+        assert_eq!(
+            error(r#"{"id":1,"score":1,"ratio":0.5,"ok":true,"payload":{"base64":"!"}}"#),
+            "field `payload`: {\"base64\":\"!\"} does not fit BYTES"
+        );
+        assert_eq!(
+            error(r#"{"id":1,"score":1,"ratio":0.5,"ok":true}"#),
+            "field `payload` is missing"
+        );
+        assert_eq!(
+            error(r#"{"id":1,"score":1,"ratio":0.5,"ok":true,"payload":"","x":1}"#),
+            "unknown field `x`"
+        );
+
+        let unnamed = Schema::new(schema.data_types()).unwrap();
+        assert!(to_json(&buffer, &unnamed).is_err());
+
+        let schema = Schema::named(&["name", "weight"], &[types::STRING, types::F32]).unwrap();
+        let mut buffer = Buffer::new();
+        {
+            let mut writer = buffer.as_writer(&schema);
+            writer.set_str("abc").unwrap();
+            writer.set_f32(0.1).unwrap();
+            writer.finish().unwrap();
+        }
+
+        let json = to_json_string(&buffer, &schema).unwrap();
+        assert_eq!(json, r#"{"name":"abc","weight":0.1}"#);
+        assert_eq!(from_json_str(json.as_str(), &schema).unwrap(), buffer);
+        assert_eq!(
+            from_json_str(r#"{"name":"abc","weight":1e39}"#, &schema)
+                .unwrap_err()
+                .to_string(),
+            "field `weight`: 1e+39 does not fit F32"
+        );
+    }
+}
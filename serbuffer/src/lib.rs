@@ -1,17 +1,47 @@
 pub mod buffer;
+pub mod checksum;
+#[cfg(feature = "tokio")]
+pub mod codec;
+pub mod compression;
+#[cfg(feature = "csv")]
+pub mod csv;
+pub mod display;
 pub mod encoding;
+pub mod file;
+pub mod io;
+#[cfg(feature = "json")]
+pub mod json;
+#[cfg(feature = "mmap")]
+pub mod mmap;
+pub mod pool;
 pub mod reader;
+pub mod schema;
+#[cfg(test)]
+pub(crate) mod test_util;
+pub mod value;
 pub mod writer;
 
 pub use buffer::types;
 pub use buffer::Buffer;
+pub use buffer::FrozenBuffer;
+pub use checksum::Checksum;
+pub use pool::BufferPool;
+pub use pool::PooledBuffer;
 pub use reader::BufferMutReader;
 pub use reader::BufferReader;
+pub use schema::Schema;
+pub use schema::SchemaRef;
+pub use value::Value;
+pub use writer::BufMutWriter;
 pub use writer::BufferWriter;
+pub use writer::TransactionalBufferWriter;
+pub use writer::UnorderedBufferWriter;
 
 #[cfg(test)]
 mod tests {
-    use crate::{types, Buffer};
+    use bytes::{BufMut, BytesMut};
+
+    use crate::{types, BufMutWriter, Buffer, BufferReader, Schema, Value};
 
     #[test]
     pub fn buffer_test() {
@@ -34,15 +64,15 @@ mod tests {
             types::I32,
         ];
 
-        let strs = vec![
+        let strs = [
             "aaaa-bbbb-cccc-dddd",
             "cccc-bbbb-aaaa",
             "aaaa-bbbb-cccc-dddd-eeee",
             "dddd-cccc-bbbb-aaaa",
             "bbbb",
         ];
-        for i in 0..strs.len() {
-            let uuid1 = strs[i];
+        for (i, uuid1) in strs.iter().enumerate() {
+            let uuid1 = *uuid1;
             let uuid2 = uuid::Uuid::new_v4().to_string();
             let uuid2 = uuid2.as_str();
 
@@ -71,9 +101,9 @@ mod tests {
 
             writer.set_i32((5 + i) as i32).unwrap();
 
-            println!("{:?}", buffer.buf.as_ref());
+            println!("{}", buffer.display(&data_types));
 
-            let mut reader = buffer.as_reader(&data_types);
+            let reader = buffer.as_reader(&data_types);
 
             assert_eq!(reader.get_bool(0).unwrap(), i % 2 == 0);
 
@@ -98,7 +128,7 @@ mod tests {
 
             assert_eq!(reader.get_i32(14).unwrap(), (5 + i) as i32);
 
-            // buffer.reset();
+            buffer.reset();
         }
     }
 
@@ -132,51 +162,571 @@ mod tests {
         {
             let mut writer = buffer0.as_writer(&data_types0);
 
-            writer.set_i8((10) as i8).unwrap();
-            writer.set_i16((12) as i16).unwrap();
-            writer.set_i32((14) as i32).unwrap();
-            writer.set_i64((16) as i64).unwrap();
-            writer.set_f32(18.001 as f32).unwrap();
+            writer.set_i8(10_i8).unwrap();
+            writer.set_i16(12_i16).unwrap();
+            writer.set_i32(14_i32).unwrap();
+            writer.set_i64(16_i64).unwrap();
+            writer.set_f32(18.001_f32).unwrap();
             writer.set_str(uuid1.as_str()).unwrap();
-            writer.set_i32((5) as i32).unwrap();
+            writer.set_i32(5_i32).unwrap();
         }
 
         {
             let mut writer = buffer1.as_writer(&data_types1);
 
-            writer.set_u8((11) as u8).unwrap();
-            writer.set_u16((13) as u16).unwrap();
-            writer.set_u32((15) as u32).unwrap();
-            writer.set_u64((17) as u64).unwrap();
-            writer.set_f64(19.002 as f64).unwrap();
+            writer.set_u8(11_u8).unwrap();
+            writer.set_u16(13_u16).unwrap();
+            writer.set_u32(15_u32).unwrap();
+            writer.set_u64(17_u64).unwrap();
+            writer.set_f64(19.002_f64).unwrap();
             writer.set_str(uuid2.as_str()).unwrap();
-            writer.set_u32((5) as u32).unwrap();
+            writer.set_u32(5_u32).unwrap();
         }
 
-        println!("{:?}", buffer0.buf.as_ref());
-        println!("{:?}", buffer1.buf.as_ref());
+        println!("{}", buffer0.display(&data_types0));
+        println!("{}", buffer1.display(&data_types1));
 
         buffer0.extend(&buffer1).unwrap();
-        println!("{:?}", buffer0.buf.as_ref());
+        println!("{}", buffer0.display(&data_types0).hex());
 
         let mut data_type_merge = data_types0.to_vec();
         data_type_merge.extend_from_slice(&data_types1);
-        let mut reader = buffer0.as_reader(data_type_merge.as_slice());
+        let reader = buffer0.as_reader(data_type_merge.as_slice());
 
-        assert_eq!(reader.get_i8(0).unwrap(), (10) as i8);
-        assert_eq!(reader.get_i16(1).unwrap(), (12) as i16);
-        assert_eq!(reader.get_i32(2).unwrap(), (14) as i32);
-        assert_eq!(reader.get_i64(3).unwrap(), (16) as i64);
-        assert_eq!(reader.get_f32(4).unwrap(), 18.001 as f32);
+        assert_eq!(reader.get_i8(0).unwrap(), 10_i8);
+        assert_eq!(reader.get_i16(1).unwrap(), 12_i16);
+        assert_eq!(reader.get_i32(2).unwrap(), 14_i32);
+        assert_eq!(reader.get_i64(3).unwrap(), 16_i64);
+        assert_eq!(reader.get_f32(4).unwrap(), 18.001_f32);
         assert_eq!(reader.get_str(5).unwrap(), uuid1);
-        assert_eq!(reader.get_i32(6).unwrap(), (5) as i32);
+        assert_eq!(reader.get_i32(6).unwrap(), 5_i32);
 
-        assert_eq!(reader.get_u8(7).unwrap(), (11) as u8);
-        assert_eq!(reader.get_u16(8).unwrap(), (13) as u16);
-        assert_eq!(reader.get_u32(9).unwrap(), (15) as u32);
-        assert_eq!(reader.get_u64(10).unwrap(), (17) as u64);
-        assert_eq!(reader.get_f64(11).unwrap(), 19.002 as f64);
+        assert_eq!(reader.get_u8(7).unwrap(), 11_u8);
+        assert_eq!(reader.get_u16(8).unwrap(), 13_u16);
+        assert_eq!(reader.get_u32(9).unwrap(), 15_u32);
+        assert_eq!(reader.get_u64(10).unwrap(), 17_u64);
+        assert_eq!(reader.get_f64(11).unwrap(), 19.002_f64);
         assert_eq!(reader.get_str(12).unwrap(), uuid2);
-        assert_eq!(reader.get_u32(13).unwrap(), (5) as u32);
+        assert_eq!(reader.get_u32(13).unwrap(), 5_u32);
+    }
+
+    #[test]
+    pub fn buf_project_test() {
+        let mut buffer = Buffer::new();
+        let data_types = [types::I32, types::BYTES, types::U64, types::BOOL];
+
+        {
+            let mut writer = buffer.as_writer(&data_types);
+            writer.set_i32(10).unwrap();
+            writer.set_str("abc").unwrap();
+            writer.set_u64(20).unwrap();
+            writer.set_bool(true).unwrap();
+        }
+
+        let (projected, projected_types) = buffer.project(&data_types, &[2, 1]).unwrap();
+        assert_eq!(projected_types, vec![types::U64, types::BYTES]);
+
+        let reader = projected.as_reader(&projected_types);
+        assert_eq!(reader.get_u64(0).unwrap(), 20);
+        assert_eq!(reader.get_str(1).unwrap(), "abc");
+
+        let mut target = Buffer::new();
+        let projected_types = buffer
+            .project_into(&data_types, &[3, 0], &mut target)
+            .unwrap();
+        assert_eq!(projected_types, vec![types::BOOL, types::I32]);
+
+        let reader = target.as_reader(&projected_types);
+        assert!(reader.get_bool(0).unwrap());
+        assert_eq!(reader.get_i32(1).unwrap(), 10);
+
+        assert!(buffer.project(&data_types, &[4]).is_err());
+
+        // truncated buffer without the position index
+        let truncated = Buffer::from(BytesMut::from(&buffer.as_slice()[..6]));
+        assert!(truncated.project(&data_types, &[0]).is_err());
+    }
+
+    #[test]
+    pub fn buf_split_join_test() {
+        let mut buffer = Buffer::new();
+        let data_types = [types::I32, types::BYTES, types::U64, types::BYTES];
+
+        {
+            let mut writer = buffer.as_writer(&data_types);
+            writer.set_i32(10).unwrap();
+            writer.set_str("abc").unwrap();
+            writer.set_u64(20).unwrap();
+            writer.set_str("def").unwrap();
+        }
+
+        let whole = buffer.clone();
+        let (mut head, mut tail) = buffer.split_at_field(&data_types, 2).unwrap();
+
+        let reader = head.as_reader(&data_types[..2]);
+        assert_eq!(reader.get_i32(0).unwrap(), 10);
+        assert_eq!(reader.get_str(1).unwrap(), "abc");
+
+        let reader = tail.as_reader(&data_types[2..]);
+        assert_eq!(reader.get_u64(0).unwrap(), 20);
+        assert_eq!(reader.get_str(1).unwrap(), "def");
+
+        let (joined, joined_types) =
+            Buffer::join(&[&head, &tail], &[&data_types[..2], &data_types[2..]]).unwrap();
+        assert_eq!(joined_types, data_types.to_vec());
+        assert_eq!(joined, whole);
+
+        assert!(head
+            .extend_checked(&data_types[..2], &tail, &data_types[2..])
+            .is_ok());
+        assert_eq!(head, whole);
+
+        assert!(tail
+            .extend_checked(&data_types[2..], &whole, &data_types[..2])
+            .is_err());
+
+        // a huge length prefix
+        let corrupt = Buffer::from(BytesMut::from(
+            &[0xFE, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0][..],
+        ));
+        assert!(corrupt.validate(&[types::BYTES, types::BOOL]).is_err());
+    }
+
+    #[test]
+    pub fn frozen_buffer_test() {
+        let mut buffer = Buffer::new();
+        let data_types = [types::I64, types::BYTES];
+
+        {
+            let mut writer = buffer.as_writer(&data_types);
+            writer.set_i64(10).unwrap();
+            writer.set_str("abc").unwrap();
+        }
+
+        let frozen = Buffer::from(buffer.buf.clone())
+            .freeze(&data_types)
+            .unwrap();
+        let frozen_clone = frozen.clone();
+
+        let reader = frozen.as_reader(&data_types);
+        let reader_clone = frozen_clone.as_reader(&data_types).clone();
+        assert_eq!(reader.get_i64(0).unwrap(), 10);
+        assert_eq!(reader_clone.get_str(1).unwrap(), "abc");
+
+        let frozen = frozen.try_into_mut().unwrap_err();
+        drop(frozen_clone);
+
+        let buffer_mut = frozen.try_into_mut().unwrap();
+        assert_eq!(buffer_mut, buffer);
+        let reader = buffer_mut.as_reader(&data_types);
+        assert_eq!(reader.get_str(1).unwrap(), "abc");
+    }
+
+    #[test]
+    pub fn shared_reader_test() {
+        fn assert_send_sync<T: Send + Sync>(_t: &T) {}
+
+        let mut buffer = Buffer::new();
+        let data_types = [types::I64, types::BYTES];
+
+        {
+            let mut writer = buffer.as_writer(&data_types);
+            writer.set_i64(10).unwrap();
+            writer.set_str("abc").unwrap();
+        }
+
+        let buffer = std::sync::Arc::new(Buffer::from(buffer.buf.clone()));
+
+        let reader0 = buffer.as_reader(&data_types);
+        let reader1 = buffer.as_reader(&data_types);
+        assert_send_sync(&reader0);
+
+        assert_eq!(reader0.get_i64(0).unwrap(), 10);
+        assert_eq!(reader1.get_str(1).unwrap(), "abc");
+
+        let handle = {
+            let buffer = buffer.clone();
+            std::thread::spawn(move || {
+                let reader = buffer.as_reader(&data_types);
+                reader.get_str(1).unwrap().to_string()
+            })
+        };
+        assert_eq!(handle.join().unwrap(), "abc");
+    }
+
+    #[test]
+    pub fn unordered_writer_test() {
+        let mut buffer = Buffer::new();
+        let data_types = [types::I32, types::BYTES, types::U64, types::BOOL];
+
+        let mut writer = buffer.as_unordered_writer(&data_types);
+        writer.set_u64(2, 20).unwrap();
+        writer.set_bool(3, true).unwrap();
+        writer.set_str(1, "abc").unwrap();
+        assert!(writer.set_i64(0, 10).is_err());
+        assert!(writer.set_i32(4, 10).is_err());
+        assert!(writer.finish().is_err());
+        assert!(buffer.is_empty());
+
+        let mut writer = buffer.as_unordered_writer(&data_types);
+        writer.set_bool(3, true).unwrap();
+        writer.set_str(1, "abc").unwrap();
+        writer.set_u64(2, 20).unwrap();
+        writer.set_i32(0, 10).unwrap();
+        writer.set_str(1, "abcd").unwrap();
+        writer.finish().unwrap();
+
+        let reader = buffer.as_reader(&data_types);
+        assert_eq!(reader.get_i32(0).unwrap(), 10);
+        assert_eq!(reader.get_str(1).unwrap(), "abcd");
+        assert_eq!(reader.get_u64(2).unwrap(), 20);
+        assert!(reader.get_bool(3).unwrap());
+    }
+
+    #[test]
+    pub fn writer_finish_test() {
+        let mut buffer = Buffer::new();
+        let data_types = [types::I32, types::BYTES, types::U64];
+
+        let mut writer = buffer.as_writer(&data_types);
+        writer.set_i32(10).unwrap();
+        writer.set_str("abc").unwrap();
+        assert!(writer.finish().is_err());
+        assert!(!buffer.is_empty());
+
+        // the plain writer keep the written fields on drop, and warn in the debug build
+        let mut buffer = Buffer::new();
+        {
+            let mut writer = buffer.as_writer(&data_types);
+            assert!(writer.drop_warning().is_none());
+            writer.set_i32(10).unwrap();
+            assert_eq!(
+                writer.drop_warning().unwrap(),
+                "serbuffer: `BufferWriter` dropped after writing 1 of 3 fields without `finish`"
+            );
+        }
+        assert_eq!(buffer.len(), 4);
+
+        let mut buffer = Buffer::new();
+        let mut writer = buffer.as_writer(&data_types);
+        writer.set_i32(10).unwrap();
+        writer.set_str("abc").unwrap();
+        writer.set_u64(20).unwrap();
+        assert!(writer.set_u64(20).is_err());
+        writer.finish().unwrap();
+    }
+
+    #[test]
+    pub fn buf_mut_writer_test() {
+        let data_types = [types::I32, types::BYTES, types::U64];
+        fn write<B: BufMut>(writer: &mut BufMutWriter<B>) -> Result<(), std::io::Error> {
+            writer.set_i32(10)?;
+            writer.set_str("abc")?;
+            writer.set_u64(20)
+        }
+
+        // a frame header followed by two records in a `Vec`
+        let mut frame = vec![0xAB];
+        let mut writer = BufMutWriter::new(&mut frame, &data_types);
+        write(&mut writer).unwrap();
+        assert!(writer.set_u64(20).is_err());
+        let (len, field_offsets) = writer.finish().unwrap();
+        assert_eq!(len, 4 + 4 + 8);
+        assert_eq!(field_offsets, vec![0, 4, 8]);
+
+        let mut writer = BufMutWriter::new(&mut frame, &data_types);
+        writer.set_i32(11).unwrap();
+        writer.set_str("abcd").unwrap();
+        writer.set_u64(21).unwrap();
+        let (second_len, field_offsets) = writer.finish().unwrap();
+        assert_eq!(second_len, 4 + 5 + 8);
+        assert_eq!(field_offsets, vec![0, 4, 9]);
+        assert_eq!(frame.len(), 1 + len + second_len);
+
+        let mut buffer = Buffer::new();
+        let mut buffer_writer = buffer.as_writer(&data_types);
+        buffer_writer.set_i32(10).unwrap();
+        buffer_writer.set_str("abc").unwrap();
+        buffer_writer.set_u64(20).unwrap();
+        buffer_writer.finish().unwrap();
+        assert_eq!(&frame[1..1 + len], buffer.as_slice());
+
+        let reader = BufferReader::from_slice(&frame[1..1 + len], &data_types);
+        assert_eq!(reader.get_str(1).unwrap(), "abc");
+        let reader = BufferReader::from_slice(&frame[1 + len..], &data_types);
+        assert_eq!(reader.get_i32(0).unwrap(), 11);
+        assert_eq!(reader.get_str(1).unwrap(), "abcd");
+        assert_eq!(reader.get_u64(2).unwrap(), 21);
+
+        // type mismatch and missing fields
+        let mut writer = BufMutWriter::new(&mut frame, &data_types);
+        assert!(writer.set_bytes(b"abc").is_err());
+        writer.set_i32(1).unwrap();
+        assert!(writer.finish().is_err());
+
+        // fixed size storage
+        let mut storage = [0u8; 20];
+        let (len, _) = {
+            let mut slice = &mut storage[..];
+            let mut writer = BufMutWriter::new(&mut slice, &data_types);
+            write(&mut writer).unwrap();
+            writer.finish().unwrap()
+        };
+        assert_eq!(&storage[..len], buffer.as_slice());
+
+        let mut storage = [0u8; 10];
+        let mut slice = &mut storage[..];
+        let mut writer = BufMutWriter::new(&mut slice, &data_types);
+        assert!(write(&mut writer).is_err());
+
+        // through a `BufMut` adaptor
+        let mut limited = Vec::new().limit(8);
+        let mut writer = BufMutWriter::new(&mut limited, &data_types);
+        writer.set_i32(10).unwrap();
+        assert!(writer.set_str("abc").is_ok());
+        assert!(writer.set_u64(20).is_err());
+    }
+
+    #[test]
+    pub fn transactional_writer_test() {
+        let mut buffer = Buffer::new();
+        let data_types = [types::I32, types::BYTES, types::U64];
+
+        {
+            let mut writer = buffer.as_transactional_writer(&data_types);
+            writer.set_i32(10).unwrap();
+            writer.set_str("abc").unwrap();
+        }
+        assert!(buffer.is_empty());
+
+        let mut writer = buffer.as_transactional_writer(&data_types);
+        writer.set_i32(10).unwrap();
+        assert!(writer.set_u64(20).is_err());
+        assert!(writer.finish().is_err());
+        assert!(buffer.is_empty());
+
+        let mut writer = buffer.as_transactional_writer(&data_types);
+        writer.set_i32(10).unwrap();
+        writer.set_str("abc").unwrap();
+        writer.set_u64(20).unwrap();
+        writer.finish().unwrap();
+        let written = buffer.clone();
+
+        // a failed record after an existing one keeps the existing one
+        let mut writer = buffer.as_transactional_writer(&data_types);
+        writer.set_i32(11).unwrap();
+        drop(writer);
+        assert_eq!(buffer, written);
+
+        let reader = buffer.as_reader(&data_types);
+        assert_eq!(reader.get_i32(0).unwrap(), 10);
+        assert_eq!(reader.get_str(1).unwrap(), "abc");
+        assert_eq!(reader.get_u64(2).unwrap(), 20);
+    }
+
+    #[test]
+    pub fn append_writer_test() {
+        let data_types = [types::I32, types::BYTES, types::U64, types::BYTES];
+
+        let mut buffer = Buffer::new();
+        {
+            let mut writer = buffer.as_writer(&data_types[..2]);
+            writer.set_i32(10).unwrap();
+            writer.set_str("abc").unwrap();
+            writer.finish().unwrap();
+        }
+
+        // without field position index
+        let mut buffer = Buffer::from(buffer.buf.clone());
+        assert!(buffer.as_append_writer(&data_types, 1).is_err());
+        assert!(buffer.as_append_writer(&data_types, 3).is_err());
+
+        let mut writer = buffer.as_append_writer(&data_types, 2).unwrap();
+        assert!(writer.set_i32(10).is_err());
+        writer.set_u64(20).unwrap();
+        writer.set_str("def").unwrap();
+        writer.finish().unwrap();
+
+        let reader = buffer.as_reader(&data_types);
+        assert_eq!(reader.get_i32(0).unwrap(), 10);
+        assert_eq!(reader.get_str(1).unwrap(), "abc");
+        assert_eq!(reader.get_u64(2).unwrap(), 20);
+        assert_eq!(reader.get_str(3).unwrap(), "def");
+
+        // `as_writer` on a buffer without field position index
+        let mut buffer = Buffer::from(buffer.buf.clone());
+        buffer.reset();
+        {
+            let mut writer = buffer.as_writer(&data_types[..2]);
+            writer.set_i32(11).unwrap();
+            writer.set_str("xyz").unwrap();
+            writer.finish().unwrap();
+        }
+        let mut buffer = Buffer::from(buffer.buf.clone());
+        {
+            let mut writer = buffer.as_writer(&data_types[2..]);
+            writer.set_u64(21).unwrap();
+            writer.set_str("uvw").unwrap();
+            writer.finish().unwrap();
+        }
+
+        let reader = buffer.as_reader(&data_types);
+        assert_eq!(reader.get_i32(0).unwrap(), 11);
+        assert_eq!(reader.get_str(1).unwrap(), "xyz");
+        assert_eq!(reader.get_u64(2).unwrap(), 21);
+        assert_eq!(reader.get_str(3).unwrap(), "uvw");
+    }
+
+    #[test]
+    pub fn get_by_name_test() {
+        let schema = Schema::named(
+            &["name", "count", "ratio"],
+            &[types::BYTES, types::I64, types::F64],
+        )
+        .unwrap();
+
+        let mut buffer = Buffer::new();
+        {
+            let mut writer = buffer.as_writer(&schema);
+            writer.set_str("abc").unwrap();
+            writer.set_i64(10).unwrap();
+            writer.set_f64(0.5).unwrap();
+            writer.finish().unwrap();
+        }
+
+        let reader = buffer.as_reader(&schema);
+        assert_eq!(reader.index_of("count").unwrap(), 1);
+        assert_eq!(reader.get_str_by_name("name").unwrap(), "abc");
+        assert_eq!(reader.get_i64_by_name("count").unwrap(), 10);
+        assert_eq!(reader.get_f64_by_name("ratio").unwrap(), 0.5);
+        assert!(reader.get_i64_by_name("unknown").is_err());
+        assert!(reader.get_i32_by_name("count").is_err());
+
+        let reader = buffer.as_reader(schema.data_types());
+        assert!(reader.get_i64_by_name("count").is_err());
+    }
+
+    #[test]
+    pub fn value_test() {
+        let data_types = [
+            types::BOOL,
+            types::I8,
+            types::U8,
+            types::I16,
+            types::U16,
+            types::I32,
+            types::U32,
+            types::I64,
+            types::U64,
+            types::F32,
+            types::F64,
+            types::BYTES,
+            types::STRING,
+        ];
+        let values = [
+            Value::Bool(true),
+            Value::I8(-8),
+            Value::U8(8),
+            Value::I16(-16),
+            Value::U16(16),
+            Value::I32(-32),
+            Value::U32(32),
+            Value::I64(-64),
+            Value::U64(64),
+            Value::F32(32.5),
+            Value::F64(64.5),
+            Value::Bytes(b"bytes"),
+            Value::Str("str"),
+        ];
+
+        let mut buffer = Buffer::new();
+        {
+            let mut writer = buffer.as_writer(&data_types);
+            for value in values.iter() {
+                writer.set(*value).unwrap();
+            }
+            writer.finish().unwrap();
+        }
+
+        let reader = buffer.as_reader(&data_types);
+        let read_values: Vec<Value> = reader.iter().map(|value| value.unwrap()).collect();
+        assert_eq!(&read_values[..12], &values[..12]);
+        assert_eq!(read_values[12], Value::Bytes(b"str"));
+        assert_eq!(read_values[12].as_str(), Some("str"));
+        assert_eq!(reader.get(5).unwrap(), Value::I32(-32));
+        assert_eq!(
+            reader.get(data_types.len()).unwrap_err().kind(),
+            std::io::ErrorKind::InvalidInput
+        );
+
+        let mut other = Buffer::new();
+        {
+            let mut writer = other.as_unordered_writer(&data_types);
+            for (index, value) in values.iter().enumerate().rev() {
+                writer.set(index, *value).unwrap();
+            }
+            assert!(writer.set(0, Value::I8(1)).is_err());
+            writer.finish().unwrap();
+        }
+        assert_eq!(other, buffer);
+
+        let mut writer = other.as_writer(&data_types);
+        assert!(writer.set(Value::I8(1)).is_err());
+    }
+
+    #[test]
+    pub fn display_test() {
+        let schema = Schema::named(
+            &["id", "name", "flag"],
+            &[types::I32, types::BYTES, types::BOOL],
+        )
+        .unwrap();
+        let mut buffer = Buffer::new();
+        {
+            let mut writer = buffer.as_writer(&schema);
+            writer.set_i32(-7).unwrap();
+            writer.set_str("abcdef").unwrap();
+            writer.set_bool(true).unwrap();
+            writer.finish().unwrap();
+        }
+
+        assert_eq!(
+            buffer.display(&schema).to_string(),
+            "[0] id: I32 = -7\n[1] name: BYTES = \"abcdef\"\n[2] flag: BOOL = true\n"
+        );
+        assert_eq!(
+            buffer.display(schema.data_types()).max_bytes(3).to_string(),
+            "[0] I32 = -7\n[1] BYTES = \"abc\"... (6 bytes)\n[2] BOOL = true\n"
+        );
+        assert_eq!(
+            buffer.display(&schema).hex().to_string(),
+            "[0] id: I32\n\
+             00000000  data  f9 ff ff ff\n\
+             [1] name: BYTES\n\
+             00000004  len   06\n\
+             00000005  data  61 62 63 64 65 66\n\
+             [2] flag: BOOL\n\
+             0000000b  data  01\n"
+        );
+
+        // corrupt length prefix
+        let mut bytes = buffer.as_slice().to_vec();
+        bytes[4] = 0x20;
+        let corrupt = Buffer::from(bytes::BytesMut::from(&bytes[..]));
+        assert_eq!(
+            corrupt.display(&schema).to_string(),
+            "[0] id: I32 = -7\n[1] name: BYTES <32 bytes at 5 out of the 12 bytes buffer>\n\
+             <8 bytes not decoded>\n"
+        );
+
+        // overflowing length prefix
+        let mut bytes = buffer.as_slice()[..4].to_vec();
+        bytes.extend_from_slice(&[0xFE, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF]);
+        let corrupt = Buffer::from(bytes::BytesMut::from(&bytes[..]));
+        assert_eq!(
+            corrupt.display(&schema).to_string(),
+            "[0] id: I32 = -7\n[1] name: BYTES <invalid length 18446744073709551615 at 4>\n\
+             <9 bytes not decoded>\n"
+        );
     }
 }
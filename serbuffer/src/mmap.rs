@@ -0,0 +1,280 @@
+//! memory mapped record files, enabled by the `mmap` feature.
+//! the records are read in place from the mapped pages, nothing is copied to the heap.
+//! only the uncompressed record files can be mapped.
+
+use std::convert::{TryFrom, TryInto};
+use std::fs::File;
+use std::io::ErrorKind;
+use std::ops::Range;
+use std::path::Path;
+use std::sync::OnceLock;
+
+use memmap2::Mmap;
+
+use crate::compression::Compression;
+use crate::encoding::read_lenenc_int;
+use crate::file::{
+    block_checksum, read_header, BLOCK_HEADER_LEN, BLOCK_TAG, COMPRESSED_BLOCK_TAG, FOOTER_TAG,
+};
+use crate::reader::BufferReader;
+use crate::Schema;
+
+/// read-only memory mapped record file, hand out the `BufferReader`s borrowing the map.
+pub struct MmapFile {
+    mmap: Mmap,
+    schema: Schema,
+    header_len: usize,
+    /// the offset of each record in the map, built by the first random access
+    record_offsets: OnceLock<Vec<usize>>,
+}
+
+impl MmapFile {
+    /// map the record file and read its header.
+    /// the file must not be truncated or modified while it is mapped.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, std::io::Error> {
+        let file = File::open(path)?;
+        let mmap = unsafe { Mmap::map(&file)? };
+
+        let (schema, compression, header_len) = read_header(&mmap[..])?;
+        if compression != Compression::None {
+            return Err(std::io::Error::new(
+                ErrorKind::Unsupported,
+                format!(
+                    "the {:?} compressed record file can not be mapped",
+                    compression
+                ),
+            ));
+        }
+
+        Ok(MmapFile {
+            mmap,
+            schema,
+            header_len,
+            record_offsets: OnceLock::new(),
+        })
+    }
+
+    /// the schema of the records.
+    pub fn schema(&self) -> &Schema {
+        &self.schema
+    }
+
+    /// the number of records, build the record index on the first call.
+    pub fn record_count(&self) -> Result<usize, std::io::Error> {
+        Ok(self.record_offsets()?.len())
+    }
+
+    /// the raw bytes of the record `n`, `None` if `n` is out of the file.
+    pub fn get_raw(&self, n: usize) -> Result<Option<&[u8]>, std::io::Error> {
+        let offset = match self.record_offsets()?.get(n) {
+            Some(offset) => *offset,
+            None => return Ok(None),
+        };
+
+        let range = record_at(&self.mmap, offset)?;
+        Ok(Some(&self.mmap[range]))
+    }
+
+    /// reader of the record `n`, `None` if `n` is out of the file.
+    pub fn get(&self, n: usize) -> Result<Option<BufferReader<'_, '_>>, std::io::Error> {
+        Ok(self
+            .get_raw(n)?
+            .map(|record| BufferReader::from_slice(record, &self.schema)))
+    }
+
+    /// iterate the records in order, the record index is not needed.
+    pub fn iter(&self) -> MmapRecords<'_> {
+        MmapRecords {
+            file: self,
+            offset: self.header_len,
+            block: 0..0,
+            block_records: 0,
+            failed: false,
+        }
+    }
+
+    fn record_offsets(&self) -> Result<&[usize], std::io::Error> {
+        if let Some(record_offsets) = self.record_offsets.get() {
+            return Ok(record_offsets.as_slice());
+        }
+
+        let mut record_offsets = Vec::new();
+        let mut offset = self.header_len;
+        while let Some((mut block, record_count, next)) = block_at(&self.mmap, offset)? {
+            for _ in 0..record_count {
+                record_offsets.push(block.start);
+                let record = record_in(&self.mmap, block.clone())?;
+                block.start = record.end;
+            }
+            offset = next;
+        }
+
+        Ok(self.record_offsets.get_or_init(|| record_offsets))
+    }
+}
+
+/// sequential records of a `MmapFile`, stop after the first error.
+pub struct MmapRecords<'a> {
+    file: &'a MmapFile,
+    /// the offset of the next block
+    offset: usize,
+    /// the unread records of the current block
+    block: Range<usize>,
+    block_records: u32,
+    failed: bool,
+}
+
+impl<'a> MmapRecords<'a> {
+    fn read(&mut self) -> Result<Option<BufferReader<'a, 'a>>, std::io::Error> {
+        let data = &self.file.mmap[..];
+        while self.block_records == 0 {
+            match block_at(data, self.offset)? {
+                Some((block, record_count, next)) => {
+                    self.block = block;
+                    self.block_records = record_count;
+                    self.offset = next;
+                }
+                None => return Ok(None),
+            }
+        }
+
+        let record = record_in(data, self.block.clone())?;
+        self.block.start = record.end;
+        self.block_records -= 1;
+        Ok(Some(BufferReader::from_slice(
+            &data[record],
+            &self.file.schema,
+        )))
+    }
+}
+
+impl<'a> Iterator for MmapRecords<'a> {
+    type Item = Result<BufferReader<'a, 'a>, std::io::Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+
+        let result = self.read().transpose();
+        if let Some(Err(_)) = result {
+            self.failed = true;
+        }
+        result
+    }
+}
+
+/// check the block at `offset`, return its payload range, record count and the next offset.
+/// `None` at the footer or the end of an unfinished file.
+fn block_at(
+    data: &[u8],
+    offset: usize,
+) -> Result<Option<(Range<usize>, u32, usize)>, std::io::Error> {
+    let header = match data.get(offset) {
+        None | Some(&FOOTER_TAG) => return Ok(None),
+        Some(&BLOCK_TAG) => data
+            .get(offset..offset.saturating_add(BLOCK_HEADER_LEN))
+            .ok_or_else(|| std::io::Error::from(ErrorKind::UnexpectedEof))?,
+        Some(&COMPRESSED_BLOCK_TAG) => {
+            return Err(std::io::Error::new(
+                ErrorKind::Unsupported,
+                format!("the compressed block at {} can not be mapped", offset),
+            ))
+        }
+        Some(_) => {
+            return Err(std::io::Error::new(
+                ErrorKind::InvalidData,
+                format!("broken block header at {}", offset),
+            ))
+        }
+    };
+
+    let payload_len = u32::from_le_bytes(header[1..5].try_into().unwrap()) as usize;
+    let record_count = u32::from_le_bytes(header[5..9].try_into().unwrap());
+    let checksum = u32::from_le_bytes(header[9..13].try_into().unwrap());
+
+    let start = offset + BLOCK_HEADER_LEN;
+    let end = start
+        .checked_add(payload_len)
+        .filter(|end| *end <= data.len())
+        .ok_or_else(|| std::io::Error::from(ErrorKind::UnexpectedEof))?;
+    let payload = &data[start..end];
+    if block_checksum(header, payload) != checksum {
+        return Err(std::io::Error::new(
+            ErrorKind::InvalidData,
+            format!("checksum mismatch of the block at {}", offset),
+        ));
+    }
+
+    Ok(Some((start..end, record_count, end)))
+}
+
+/// the range of the length prefixed record at the start of `block`.
+fn record_in(data: &[u8], block: Range<usize>) -> Result<Range<usize>, std::io::Error> {
+    record_at(&data[..block.end], block.start)
+}
+
+/// the range of the length prefixed record at `offset`.
+fn record_at(data: &[u8], offset: usize) -> Result<Range<usize>, std::io::Error> {
+    let (len, prefix_len) = read_lenenc_int(data, offset)?;
+    let start = offset + prefix_len;
+    let end = usize::try_from(len)
+        .ok()
+        .and_then(|len| start.checked_add(len))
+        .filter(|end| *end <= data.len())
+        .ok_or_else(|| {
+            std::io::Error::new(ErrorKind::InvalidData, "the record is out of the block")
+        })?;
+
+    Ok(start..end)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+
+    use crate::compression::Compression;
+    use crate::file::FileWriterBuilder;
+    use crate::mmap::MmapFile;
+    use crate::test_util::{name, schema, write_file};
+
+    #[test]
+    pub fn mmap_file_test() {
+        let schema = schema();
+        let path = std::env::temp_dir().join(format!("serbuffer-mmap-{}.sbuf", std::process::id()));
+        write_file(&schema, 20, File::create(&path).unwrap());
+
+        let file = MmapFile::open(&path).unwrap();
+        assert_eq!(file.schema(), &schema);
+
+        let mut n = 0;
+        for reader in file.iter() {
+            let reader = reader.unwrap();
+            assert_eq!(reader.get_i64(0).unwrap(), n);
+            assert_eq!(reader.get_str(1).unwrap(), name(n));
+            n += 1;
+        }
+        assert_eq!(n, 20);
+
+        assert_eq!(file.record_count().unwrap(), 20);
+        let reader = file.get(13).unwrap().unwrap();
+        assert_eq!(reader.get_i64_by_name("id").unwrap(), 13);
+        assert_eq!(reader.get_str_by_name("name").unwrap(), name(13));
+        assert!(file.get(20).unwrap().is_none());
+        assert_eq!(file.get_raw(0).unwrap().unwrap().len(), 8 + 1 + 6);
+        drop(file);
+
+        // compressed files are not mapped
+        if Compression::Lz4.check_enabled().is_ok() {
+            FileWriterBuilder::new(&schema)
+                .compression(Compression::Lz4)
+                .build(File::create(&path).unwrap())
+                .unwrap()
+                .finish()
+                .unwrap();
+            assert!(MmapFile::open(&path).is_err());
+        }
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,306 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::ops::{Deref, DerefMut};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, Weak};
+
+use crate::Buffer;
+
+static POOL_ID: AtomicUsize = AtomicUsize::new(0);
+
+thread_local! {
+    /// per thread buffers of each pool, the fast path without lock.
+    /// the buffers of the dropped pools are released by the next access of the thread.
+    static LOCAL_BUFFERS: RefCell<HashMap<usize, LocalBuffers>> = RefCell::new(HashMap::new());
+}
+
+struct LocalBuffers {
+    pool: Weak<PoolInner>,
+    buffers: Vec<Buffer>,
+}
+
+/// build a `BufferPool`
+/// eg: BufferPoolBuilder::new(types::min_encoded_size(&FIELD_TYPE)).max_buffers(1024).build()
+pub struct BufferPoolBuilder {
+    buffer_capacity: usize,
+    max_buffers: usize,
+    max_capacity: usize,
+    local_buffers: usize,
+}
+
+impl BufferPoolBuilder {
+    /// `buffer_capacity` is the initial capacity of the new allocated buffers.
+    pub fn new(buffer_capacity: usize) -> Self {
+        BufferPoolBuilder {
+            buffer_capacity,
+            max_buffers: 1024,
+            max_capacity: 64 * 1024,
+            local_buffers: 16,
+        }
+    }
+
+    /// max buffers kept by the shared pool, exclude the thread local buffers.
+    pub fn max_buffers(&mut self, max_buffers: usize) -> &mut Self {
+        self.max_buffers = max_buffers;
+        self
+    }
+
+    /// buffers grown beyond `max_capacity` are released instead of returned to the pool.
+    pub fn max_capacity(&mut self, max_capacity: usize) -> &mut Self {
+        self.max_capacity = max_capacity;
+        self
+    }
+
+    /// max buffers kept by each thread, `0` disable the thread local fast path.
+    pub fn local_buffers(&mut self, local_buffers: usize) -> &mut Self {
+        self.local_buffers = local_buffers;
+        self
+    }
+
+    pub fn build(&self) -> BufferPool {
+        BufferPool {
+            inner: Arc::new(PoolInner {
+                id: POOL_ID.fetch_add(1, Ordering::Relaxed),
+                buffer_capacity: self.buffer_capacity,
+                max_buffers: self.max_buffers,
+                max_capacity: self.max_capacity,
+                local_buffers: self.local_buffers,
+                buffers: Mutex::new(Vec::new()),
+            }),
+        }
+    }
+}
+
+struct PoolInner {
+    id: usize,
+    buffer_capacity: usize,
+    max_buffers: usize,
+    max_capacity: usize,
+    local_buffers: usize,
+    buffers: Mutex<Vec<Buffer>>,
+}
+
+impl Drop for PoolInner {
+    fn drop(&mut self) {
+        let id = self.id;
+        let _ = LOCAL_BUFFERS.try_with(|local| {
+            if let Ok(mut local) = local.try_borrow_mut() {
+                local.remove(&id);
+            }
+        });
+    }
+}
+
+/// pool of cleared `Buffer`s, reuse the allocation across records.
+/// clone is cheap, all clones share the same pool.
+#[derive(Clone)]
+pub struct BufferPool {
+    inner: Arc<PoolInner>,
+}
+
+impl BufferPool {
+    pub fn new(buffer_capacity: usize) -> Self {
+        BufferPoolBuilder::new(buffer_capacity).build()
+    }
+
+    /// get a cleared buffer, it is returned to the pool when the `PooledBuffer` is dropped.
+    pub fn get(&self) -> PooledBuffer {
+        let buffer = self
+            .get_local()
+            .or_else(|| self.inner.buffers.lock().unwrap().pop())
+            .unwrap_or_else(|| Buffer::with_capacity(self.inner.buffer_capacity));
+
+        PooledBuffer {
+            buffer: Some(buffer),
+            pool: self.clone(),
+        }
+    }
+
+    /// number of buffers in the shared pool, exclude the thread local buffers.
+    pub fn len(&self) -> usize {
+        self.inner.buffers.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn get_local(&self) -> Option<Buffer> {
+        if self.inner.local_buffers == 0 {
+            return None;
+        }
+
+        self.with_local(|buffers| buffers.pop()).flatten()
+    }
+
+    /// run `f` on the thread local buffers of this pool, `None` if the thread is exiting.
+    fn with_local<R>(&self, f: impl FnOnce(&mut Vec<Buffer>) -> R) -> Option<R> {
+        LOCAL_BUFFERS
+            .try_with(|local| {
+                let mut local = local.borrow_mut();
+                if !local.contains_key(&self.inner.id) {
+                    // the pools dropped by other threads can only clear their own thread locals,
+                    // the stale entries are pruned when a pool is first used on this thread
+                    local.retain(|_, local| local.pool.strong_count() > 0);
+                }
+
+                let local = local.entry(self.inner.id).or_insert_with(|| LocalBuffers {
+                    pool: Arc::downgrade(&self.inner),
+                    buffers: Vec::new(),
+                });
+                f(&mut local.buffers)
+            })
+            .ok()
+    }
+
+    fn put(&self, mut buffer: Buffer) {
+        if buffer.buf.capacity() > self.inner.max_capacity {
+            return;
+        }
+        buffer.reset();
+
+        let buffer = if self.inner.local_buffers > 0 {
+            match self.with_local(|buffers| {
+                if buffers.len() < self.inner.local_buffers {
+                    buffers.push(buffer);
+                    None
+                } else {
+                    Some(buffer)
+                }
+            }) {
+                Some(None) | None => return,
+                Some(Some(buffer)) => buffer,
+            }
+        } else {
+            buffer
+        };
+
+        let mut buffers = self.inner.buffers.lock().unwrap();
+        if buffers.len() < self.inner.max_buffers {
+            buffers.push(buffer);
+        }
+    }
+}
+
+/// `Buffer` borrowed from a `BufferPool`, returned to the pool on drop.
+pub struct PooledBuffer {
+    buffer: Option<Buffer>,
+    pool: BufferPool,
+}
+
+impl PooledBuffer {
+    /// take the buffer out of the pool, it is not returned on drop.
+    pub fn into_inner(mut self) -> Buffer {
+        self.buffer.take().unwrap()
+    }
+}
+
+impl Deref for PooledBuffer {
+    type Target = Buffer;
+
+    fn deref(&self) -> &Self::Target {
+        self.buffer.as_ref().unwrap()
+    }
+}
+
+impl DerefMut for PooledBuffer {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.buffer.as_mut().unwrap()
+    }
+}
+
+impl Drop for PooledBuffer {
+    fn drop(&mut self) {
+        if let Some(buffer) = self.buffer.take() {
+            self.pool.put(buffer);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::mpsc;
+
+    use crate::pool::{BufferPool, BufferPoolBuilder, LOCAL_BUFFERS};
+    use crate::types;
+
+    #[test]
+    pub fn buffer_pool_test() {
+        let data_types = [types::I64, types::BYTES];
+        let pool = BufferPoolBuilder::new(types::min_encoded_size(&data_types))
+            .local_buffers(1)
+            .max_buffers(1)
+            .max_capacity(1024)
+            .build();
+
+        let mut buffer = pool.get();
+        {
+            let mut writer = buffer.as_writer(&data_types);
+            writer.set_i64(10).unwrap();
+            writer.set_str("abc").unwrap();
+            writer.finish().unwrap();
+        }
+        let ptr = buffer.as_slice().as_ptr();
+
+        // thread local fast path
+        drop(buffer);
+        assert!(pool.is_empty());
+
+        let buffer = pool.get();
+        assert!(buffer.is_empty());
+        assert_eq!(buffer.as_slice().as_ptr(), ptr);
+
+        // the local cache is full, return to the shared pool
+        let buffer1 = pool.get();
+        drop(buffer);
+        drop(buffer1);
+        assert_eq!(pool.len(), 1);
+
+        // the pool is full, release the buffer
+        let buffer0 = pool.get();
+        let buffer1 = pool.get();
+        let buffer2 = pool.get();
+        drop(buffer0);
+        drop(buffer1);
+        drop(buffer2);
+        assert_eq!(pool.len(), 1);
+
+        // too large to keep
+        let pool = BufferPool::new(16);
+        let buffer = pool.get();
+        let mut large = pool.get();
+        large.buf.reserve(128 * 1024);
+        drop(large);
+        drop(buffer);
+        let buffer = pool.get();
+        assert!(buffer.buf.capacity() < 128 * 1024);
+    }
+
+    #[test]
+    pub fn buffer_pool_drop_test() {
+        let local_pools = || LOCAL_BUFFERS.with(|local| local.borrow().len());
+
+        let pool = BufferPool::new(16);
+        let (used_sender, used_receiver) = mpsc::channel();
+        let (dropped_sender, dropped_receiver) = mpsc::channel();
+        std::thread::scope(|scope| {
+            let thread_pool = pool.clone();
+            scope.spawn(move || {
+                drop(thread_pool.get());
+                assert_eq!(local_pools(), 1);
+                drop(thread_pool);
+                used_sender.send(()).unwrap();
+
+                // the last clone is dropped by the other thread, release the stale buffers
+                dropped_receiver.recv().unwrap();
+                let pool = BufferPool::new(16);
+                drop(pool.get());
+                assert_eq!(local_pools(), 1);
+            });
+
+            used_receiver.recv().unwrap();
+            drop(pool);
+            dropped_sender.send(()).unwrap();
+        });
+    }
+}
@@ -1,18 +1,101 @@
+use std::convert::TryFrom;
 use std::io::ErrorKind;
+use std::ops::Range;
+use std::sync::OnceLock;
 
+use crate::buffer::field_pos_index;
 use crate::encoding::read_lenenc_int;
+use crate::schema::{Schema, SchemaRef};
+use crate::value::Value;
 use crate::{types, Buffer};
 
+#[derive(Clone, Debug)]
 pub struct BufferReader<'a, 'b> {
-    raw_buffer: &'a Buffer,
+    buf: &'a [u8],
+    field_pos_index: FieldPosIndex<'a>,
     data_types: &'b [u8],
+    schema: Option<&'b Schema>,
+}
+
+#[derive(Clone, Debug)]
+enum FieldPosIndex<'a> {
+    /// borrowed from the `Buffer` cache
+    Borrowed(&'a [usize]),
+    /// built by the first field access of the reader over a slice
+    Lazy(OnceLock<Vec<usize>>),
 }
 
 impl<'a, 'b> BufferReader<'a, 'b> {
-    pub(crate) fn new(raw_buffer: &'a Buffer, data_types: &'b [u8]) -> Self {
+    pub(crate) fn new(buf: &'a [u8], field_pos_index: &'a [usize], schema: SchemaRef<'b>) -> Self {
         BufferReader {
-            data_types,
-            raw_buffer,
+            buf,
+            field_pos_index: FieldPosIndex::Borrowed(field_pos_index),
+            data_types: schema.data_types(),
+            schema: schema.schema(),
+        }
+    }
+
+    /// reader over a borrowed record, such as a `&[u8]` of a mapped file, a `Bytes` network
+    /// frame or a `Vec<u8>`, no copy to a `Buffer` is needed.
+    /// the field position index is built by walking `buf` on the first field access, a corrupt
+    /// record fail the access.
+    pub fn from_slice<T: AsRef<[u8]> + ?Sized>(
+        buf: &'a T,
+        schema: impl Into<SchemaRef<'b>>,
+    ) -> Self {
+        let schema = schema.into();
+        BufferReader {
+            buf: buf.as_ref(),
+            field_pos_index: FieldPosIndex::Lazy(OnceLock::new()),
+            data_types: schema.data_types(),
+            schema: schema.schema(),
+        }
+    }
+
+    /// the `Schema` if the reader is created with one.
+    pub fn schema(&self) -> Option<&'b Schema> {
+        self.schema
+    }
+
+    pub fn data_types(&self) -> &'b [u8] {
+        self.data_types
+    }
+
+    /// the index of the field named `name`, fail if the reader has no named `Schema`.
+    pub fn index_of(&self, name: &str) -> Result<usize, std::io::Error> {
+        self.schema
+            .and_then(|schema| schema.index_of(name))
+            .ok_or_else(|| {
+                std::io::Error::new(ErrorKind::InvalidInput, format!("unknown field `{}`", name))
+            })
+    }
+
+    fn field_pos_index(&self) -> Result<&[usize], std::io::Error> {
+        match &self.field_pos_index {
+            FieldPosIndex::Borrowed(index) => Ok(index),
+            FieldPosIndex::Lazy(index) => {
+                if let Some(index) = index.get() {
+                    return Ok(index.as_slice());
+                }
+
+                let schema = match self.schema {
+                    Some(schema) => SchemaRef::from(schema),
+                    None => SchemaRef::from(self.data_types),
+                };
+                let field_pos_index = field_pos_index(self.buf, schema)?;
+                Ok(index.get_or_init(|| field_pos_index))
+            }
+        }
+    }
+
+    /// the position of the field `index` in `buf`, fail if `index` is out of range.
+    fn field_start(&self, index: usize) -> Result<usize, std::io::Error> {
+        match self.field_pos_index()?.get(index) {
+            Some(start) if index < self.data_types.len() => Ok(*start),
+            _ => Err(std::io::Error::new(
+                ErrorKind::InvalidInput,
+                format!("field index {} is out of range", index),
+            )),
         }
     }
 
@@ -23,7 +106,12 @@ impl<'a, 'b> BufferReader<'a, 'b> {
         field_len: usize,
         data_type: u8,
     ) -> Result<(), std::io::Error> {
-        if self.raw_buffer.field_pos_index[index] + field_len > self.raw_buffer.buf_len {
+        let start = self.field_start(index)?;
+        if start
+            .checked_add(field_len)
+            .filter(|end| *end <= self.buf.len())
+            .is_none()
+        {
             return Err(std::io::Error::from(ErrorKind::UnexpectedEof));
         }
 
@@ -37,13 +125,8 @@ impl<'a, 'b> BufferReader<'a, 'b> {
     pub fn get_bool(&self, index: usize) -> Result<bool, std::io::Error> {
         self.index_out_of_bounds_check(index, 1, types::BOOL)?;
 
-        let start = self.raw_buffer.field_pos_index[index];
-        let s = self
-            .raw_buffer
-            .buf
-            .get(start..start + 1)
-            .map(|x| x[0] == 1)
-            .unwrap();
+        let start = self.field_pos_index()?[index];
+        let s = self.buf.get(start..start + 1).map(|x| x[0] == 1).unwrap();
 
         Ok(s)
     }
@@ -51,13 +134,8 @@ impl<'a, 'b> BufferReader<'a, 'b> {
     pub fn get_i8(&self, index: usize) -> Result<i8, std::io::Error> {
         self.index_out_of_bounds_check(index, 1, types::I8)?;
 
-        let start = self.raw_buffer.field_pos_index[index];
-        let s = self
-            .raw_buffer
-            .buf
-            .get(start..start + 1)
-            .map(|x| x[0] as i8)
-            .unwrap();
+        let start = self.field_pos_index()?[index];
+        let s = self.buf.get(start..start + 1).map(|x| x[0] as i8).unwrap();
 
         Ok(s)
     }
@@ -65,13 +143,8 @@ impl<'a, 'b> BufferReader<'a, 'b> {
     pub fn get_u8(&self, index: usize) -> Result<u8, std::io::Error> {
         self.index_out_of_bounds_check(index, 1, types::U8)?;
 
-        let start = self.raw_buffer.field_pos_index[index];
-        let s = self
-            .raw_buffer
-            .buf
-            .get(start..start + 1)
-            .map(|x| x[0])
-            .unwrap();
+        let start = self.field_pos_index()?[index];
+        let s = self.buf.get(start..start + 1).map(|x| x[0]).unwrap();
 
         Ok(s)
     }
@@ -79,9 +152,8 @@ impl<'a, 'b> BufferReader<'a, 'b> {
     pub fn get_i16(&self, index: usize) -> Result<i16, std::io::Error> {
         self.index_out_of_bounds_check(index, 2, types::I16)?;
 
-        let start = self.raw_buffer.field_pos_index[index];
+        let start = self.field_pos_index()?[index];
         let s = self
-            .raw_buffer
             .buf
             .get(start..start + 2)
             .map(|x| unsafe { i16::from_le_bytes(*(x as *const _ as *const [_; 2])) })
@@ -93,9 +165,8 @@ impl<'a, 'b> BufferReader<'a, 'b> {
     pub fn get_u16(&self, index: usize) -> Result<u16, std::io::Error> {
         self.index_out_of_bounds_check(index, 2, types::U16)?;
 
-        let start = self.raw_buffer.field_pos_index[index];
+        let start = self.field_pos_index()?[index];
         let s = self
-            .raw_buffer
             .buf
             .get(start..start + 2)
             .map(|x| unsafe { u16::from_le_bytes(*(x as *const _ as *const [_; 2])) })
@@ -107,9 +178,8 @@ impl<'a, 'b> BufferReader<'a, 'b> {
     pub fn get_i32(&self, index: usize) -> Result<i32, std::io::Error> {
         self.index_out_of_bounds_check(index, 4, types::I32)?;
 
-        let start = self.raw_buffer.field_pos_index[index];
+        let start = self.field_pos_index()?[index];
         let s = self
-            .raw_buffer
             .buf
             .get(start..start + 4)
             .map(|x| unsafe { i32::from_le_bytes(*(x as *const _ as *const [_; 4])) })
@@ -121,9 +191,8 @@ impl<'a, 'b> BufferReader<'a, 'b> {
     pub fn get_u32(&self, index: usize) -> Result<u32, std::io::Error> {
         self.index_out_of_bounds_check(index, 4, types::U32)?;
 
-        let start = self.raw_buffer.field_pos_index[index];
+        let start = self.field_pos_index()?[index];
         let s = self
-            .raw_buffer
             .buf
             .get(start..start + 4)
             .map(|x| unsafe { u32::from_le_bytes(*(x as *const _ as *const [_; 4])) })
@@ -135,9 +204,8 @@ impl<'a, 'b> BufferReader<'a, 'b> {
     pub fn get_i64(&self, index: usize) -> Result<i64, std::io::Error> {
         self.index_out_of_bounds_check(index, 8, types::I64)?;
 
-        let start = self.raw_buffer.field_pos_index[index];
+        let start = self.field_pos_index()?[index];
         let s = self
-            .raw_buffer
             .buf
             .get(start..start + 8)
             .map(|x| unsafe { i64::from_le_bytes(*(x as *const _ as *const [_; 8])) })
@@ -149,9 +217,8 @@ impl<'a, 'b> BufferReader<'a, 'b> {
     pub fn get_u64(&self, index: usize) -> Result<u64, std::io::Error> {
         self.index_out_of_bounds_check(index, 8, types::U64)?;
 
-        let start = self.raw_buffer.field_pos_index[index];
+        let start = self.field_pos_index()?[index];
         let s = self
-            .raw_buffer
             .buf
             .get(start..start + 8)
             .map(|x| unsafe { u64::from_le_bytes(*(x as *const _ as *const [_; 8])) })
@@ -160,12 +227,11 @@ impl<'a, 'b> BufferReader<'a, 'b> {
         Ok(s)
     }
 
-    pub fn get_f32(&mut self, index: usize) -> Result<f32, std::io::Error> {
+    pub fn get_f32(&self, index: usize) -> Result<f32, std::io::Error> {
         self.index_out_of_bounds_check(index, 4, types::F32)?;
 
-        let start = self.raw_buffer.field_pos_index[index];
+        let start = self.field_pos_index()?[index];
         let s = self
-            .raw_buffer
             .buf
             .get(start..start + 4)
             .map(|x| unsafe { f32::from_le_bytes(*(x as *const _ as *const [_; 4])) })
@@ -177,9 +243,8 @@ impl<'a, 'b> BufferReader<'a, 'b> {
     pub fn get_f64(&self, index: usize) -> Result<f64, std::io::Error> {
         self.index_out_of_bounds_check(index, 8, types::F64)?;
 
-        let start = self.raw_buffer.field_pos_index[index];
+        let start = self.field_pos_index()?[index];
         let s = self
-            .raw_buffer
             .buf
             .get(start..start + 8)
             .map(|x| unsafe { f64::from_le_bytes(*(x as *const _ as *const [_; 8])) })
@@ -197,21 +262,17 @@ impl<'a, 'b> BufferReader<'a, 'b> {
     }
 
     pub fn get_bytes(&self, index: usize) -> Result<&'a [u8], std::io::Error> {
-        let start = self.raw_buffer.field_pos_index[index];
-        let (v, len_length) = read_lenenc_int(&self.raw_buffer.buf, start)?;
-
-        let len = v as usize;
-
-        self.index_out_of_bounds_check(index, len + len_length, types::BYTES)?;
-
-        let start = start + len_length;
+        self.field_start(index)?;
+        if self.data_types[index] != types::BYTES {
+            return Err(std::io::Error::from(ErrorKind::InvalidData));
+        }
 
-        let s = self.raw_buffer.buf.get(start..start + len).unwrap();
-        Ok(s)
+        let range = self.field_range(index)?;
+        Ok(&self.buf[range])
     }
 
     // pub fn get_bytes(&self, index: usize) -> Result<&'a [u8], std::io::Error> {
-    //     let start = self.raw_buffer.field_pos_index[index];
+    //     let start = self.field_pos_index[index];
     //     let s = self
     //         .raw_buffer
     //         .buf
@@ -225,21 +286,116 @@ impl<'a, 'b> BufferReader<'a, 'b> {
     //
     //     let start = start + 4;
     //
-    //     let s = self.raw_buffer.buf.get(start..start + len).unwrap();
+    //     let s = self.buf.get(start..start + len).unwrap();
     //     Ok(s)
     // }
 
-    pub fn get_bytes_raw(&self, index: usize) -> Result<&[u8], std::io::Error> {
+    pub fn get_bytes_raw(&self, index: usize) -> Result<&'a [u8], std::io::Error> {
+        let range = self.field_range(index)?;
+        Ok(&self.buf[range])
+    }
+
+    pub fn get_bool_by_name(&self, name: &str) -> Result<bool, std::io::Error> {
+        self.get_bool(self.index_of(name)?)
+    }
+
+    pub fn get_i8_by_name(&self, name: &str) -> Result<i8, std::io::Error> {
+        self.get_i8(self.index_of(name)?)
+    }
+
+    pub fn get_u8_by_name(&self, name: &str) -> Result<u8, std::io::Error> {
+        self.get_u8(self.index_of(name)?)
+    }
+
+    pub fn get_i16_by_name(&self, name: &str) -> Result<i16, std::io::Error> {
+        self.get_i16(self.index_of(name)?)
+    }
+
+    pub fn get_u16_by_name(&self, name: &str) -> Result<u16, std::io::Error> {
+        self.get_u16(self.index_of(name)?)
+    }
+
+    pub fn get_i32_by_name(&self, name: &str) -> Result<i32, std::io::Error> {
+        self.get_i32(self.index_of(name)?)
+    }
+
+    pub fn get_u32_by_name(&self, name: &str) -> Result<u32, std::io::Error> {
+        self.get_u32(self.index_of(name)?)
+    }
+
+    pub fn get_i64_by_name(&self, name: &str) -> Result<i64, std::io::Error> {
+        self.get_i64(self.index_of(name)?)
+    }
+
+    pub fn get_u64_by_name(&self, name: &str) -> Result<u64, std::io::Error> {
+        self.get_u64(self.index_of(name)?)
+    }
+
+    pub fn get_f32_by_name(&self, name: &str) -> Result<f32, std::io::Error> {
+        self.get_f32(self.index_of(name)?)
+    }
+
+    pub fn get_f64_by_name(&self, name: &str) -> Result<f64, std::io::Error> {
+        self.get_f64(self.index_of(name)?)
+    }
+
+    pub fn get_str_by_name(&self, name: &str) -> Result<&'a str, std::io::Error> {
+        self.get_str(self.index_of(name)?)
+    }
+
+    pub fn get_bytes_by_name(&self, name: &str) -> Result<&'a [u8], std::io::Error> {
+        self.get_bytes(self.index_of(name)?)
+    }
+
+    /// read the field as a `Value` by its data type, `BYTES` is read as `Value::Bytes`.
+    pub fn get(&self, index: usize) -> Result<Value<'a>, std::io::Error> {
+        self.field_start(index)?;
+        let value = match self.data_types[index] {
+            types::BOOL => Value::Bool(self.get_bool(index)?),
+            types::I8 => Value::I8(self.get_i8(index)?),
+            types::U8 => Value::U8(self.get_u8(index)?),
+            types::I16 => Value::I16(self.get_i16(index)?),
+            types::U16 => Value::U16(self.get_u16(index)?),
+            types::I32 => Value::I32(self.get_i32(index)?),
+            types::U32 => Value::U32(self.get_u32(index)?),
+            types::I64 => Value::I64(self.get_i64(index)?),
+            types::U64 => Value::U64(self.get_u64(index)?),
+            types::F32 => Value::F32(self.get_f32(index)?),
+            types::F64 => Value::F64(self.get_f64(index)?),
+            types::BYTES => Value::Bytes(self.get_bytes(index)?),
+            _ => return Err(std::io::Error::from(ErrorKind::InvalidData)),
+        };
+
+        Ok(value)
+    }
+
+    /// read all fields as `Value` in schema order.
+    pub fn iter(&self) -> impl Iterator<Item = Result<Value<'a>, std::io::Error>> + '_ {
+        (0..self.data_types.len()).map(move |index| self.get(index))
+    }
+
+    /// the data range of the field in `buf`, exclude the length prefix of `BYTES`.
+    pub(crate) fn field_range(&self, index: usize) -> Result<Range<usize>, std::io::Error> {
+        let start = self.field_start(index)?;
         let data_type = self.data_types[index];
         if data_type == types::BYTES {
-            self.get_bytes(index)
+            let (v, len_length) = read_lenenc_int(self.buf, start)?;
+            let len =
+                usize::try_from(v).map_err(|_| std::io::Error::from(ErrorKind::UnexpectedEof))?;
+            let field_len = len
+                .checked_add(len_length)
+                .ok_or_else(|| std::io::Error::from(ErrorKind::UnexpectedEof))?;
+
+            self.index_out_of_bounds_check(index, field_len, types::BYTES)?;
+
+            let start = start + len_length;
+            Ok(start..start + len)
         } else {
             let len = types::len(data_type) as usize;
-            let start = self.raw_buffer.field_pos_index[index];
 
-            let s = self.raw_buffer.buf.get(start..start + len).unwrap();
+            self.index_out_of_bounds_check(index, len, data_type)?;
 
-            Ok(s)
+            Ok(start..start + len)
         }
     }
 }
@@ -248,260 +404,285 @@ impl<'a, 'b> BufferReader<'a, 'b> {
 
 pub struct BufferMutReader<'a, 'b> {
     raw_buffer: &'a mut Buffer,
-    data_types: &'b [u8],
+    schema: SchemaRef<'b>,
 }
 
 impl<'a, 'b> BufferMutReader<'a, 'b> {
-    pub(crate) fn new(raw_buffer: &'a mut Buffer, data_types: &'b [u8]) -> Self {
-        BufferMutReader {
-            data_types,
-            raw_buffer,
-        }
+    pub(crate) fn new(raw_buffer: &'a mut Buffer, schema: SchemaRef<'b>) -> Self {
+        BufferMutReader { raw_buffer, schema }
     }
 
+    /// read only view of the buffer, share the decoding with `BufferReader`.
     #[inline]
-    fn index_out_of_bounds_check(
-        &self,
-        index: usize,
-        field_len: usize,
-        data_type: u8,
-    ) -> Result<(), std::io::Error> {
-        if self.raw_buffer.field_pos_index[index] + field_len > self.raw_buffer.buf_len {
-            return Err(std::io::Error::from(ErrorKind::UnexpectedEof));
-        }
-
-        if self.data_types[index] != data_type {
-            return Err(std::io::Error::from(ErrorKind::InvalidData));
-        }
-
-        Ok(())
+    fn reader(&self) -> BufferReader<'_, 'b> {
+        BufferReader::new(
+            self.raw_buffer.buf.as_ref(),
+            self.raw_buffer.field_pos_index(),
+            self.schema,
+        )
     }
 
     pub fn get_bool(&mut self, index: usize) -> Result<bool, std::io::Error> {
-        self.index_out_of_bounds_check(index, 1, types::BOOL)?;
-
-        let start = self.raw_buffer.field_pos_index[index];
-        let s = self
-            .raw_buffer
-            .buf
-            .get(start..start + 1)
-            .map(|x| x[0] == 1)
-            .unwrap();
-
-        Ok(s)
+        self.reader().get_bool(index)
     }
 
     pub fn get_i8(&mut self, index: usize) -> Result<i8, std::io::Error> {
-        self.index_out_of_bounds_check(index, 1, types::I8)?;
-
-        let start = self.raw_buffer.field_pos_index[index];
-        let s = self
-            .raw_buffer
-            .buf
-            .get(start..start + 1)
-            .map(|x| x[0] as i8)
-            .unwrap();
-
-        Ok(s)
+        self.reader().get_i8(index)
     }
 
     pub fn get_u8(&mut self, index: usize) -> Result<u8, std::io::Error> {
-        self.index_out_of_bounds_check(index, 1, types::U8)?;
-
-        let start = self.raw_buffer.field_pos_index[index];
-        let s = self
-            .raw_buffer
-            .buf
-            .get(start..start + 1)
-            .map(|x| x[0])
-            .unwrap();
-
-        Ok(s)
+        self.reader().get_u8(index)
     }
 
     pub fn get_i16(&mut self, index: usize) -> Result<i16, std::io::Error> {
-        self.index_out_of_bounds_check(index, 2, types::I16)?;
-
-        let start = self.raw_buffer.field_pos_index[index];
-        let s = self
-            .raw_buffer
-            .buf
-            .get(start..start + 2)
-            .map(|x| unsafe { i16::from_le_bytes(*(x as *const _ as *const [_; 2])) })
-            .unwrap();
-
-        Ok(s)
+        self.reader().get_i16(index)
     }
 
     pub fn get_u16(&mut self, index: usize) -> Result<u16, std::io::Error> {
-        self.index_out_of_bounds_check(index, 2, types::U16)?;
-
-        let start = self.raw_buffer.field_pos_index[index];
-        let s = self
-            .raw_buffer
-            .buf
-            .get(start..start + 2)
-            .map(|x| unsafe { u16::from_le_bytes(*(x as *const _ as *const [_; 2])) })
-            .unwrap();
-
-        Ok(s)
+        self.reader().get_u16(index)
     }
 
     pub fn get_i32(&mut self, index: usize) -> Result<i32, std::io::Error> {
-        self.index_out_of_bounds_check(index, 4, types::I32)?;
-
-        let start = self.raw_buffer.field_pos_index[index];
-        let s = self
-            .raw_buffer
-            .buf
-            .get(start..start + 4)
-            .map(|x| unsafe { i32::from_le_bytes(*(x as *const _ as *const [_; 4])) })
-            .unwrap();
-
-        Ok(s)
+        self.reader().get_i32(index)
     }
 
     pub fn get_u32(&mut self, index: usize) -> Result<u32, std::io::Error> {
-        self.index_out_of_bounds_check(index, 4, types::U32)?;
-
-        let start = self.raw_buffer.field_pos_index[index];
-        let s = self
-            .raw_buffer
-            .buf
-            .get(start..start + 4)
-            .map(|x| unsafe { u32::from_le_bytes(*(x as *const _ as *const [_; 4])) })
-            .unwrap();
-
-        Ok(s)
+        self.reader().get_u32(index)
     }
 
     pub fn get_i64(&mut self, index: usize) -> Result<i64, std::io::Error> {
-        self.index_out_of_bounds_check(index, 8, types::I64)?;
-
-        let start = self.raw_buffer.field_pos_index[index];
-        let s = self
-            .raw_buffer
-            .buf
-            .get(start..start + 8)
-            .map(|x| unsafe { i64::from_le_bytes(*(x as *const _ as *const [_; 8])) })
-            .unwrap();
-
-        Ok(s)
+        self.reader().get_i64(index)
     }
 
     pub fn get_u64(&mut self, index: usize) -> Result<u64, std::io::Error> {
-        self.index_out_of_bounds_check(index, 8, types::U64)?;
-
-        let start = self.raw_buffer.field_pos_index[index];
-        let s = self
-            .raw_buffer
-            .buf
-            .get(start..start + 8)
-            .map(|x| unsafe { u64::from_le_bytes(*(x as *const _ as *const [_; 8])) })
-            .unwrap();
-
-        Ok(s)
+        self.reader().get_u64(index)
     }
 
     pub fn get_f32(&mut self, index: usize) -> Result<f32, std::io::Error> {
-        self.index_out_of_bounds_check(index, 4, types::F32)?;
-
-        let start = self.raw_buffer.field_pos_index[index];
-        let s = self
-            .raw_buffer
-            .buf
-            .get(start..start + 4)
-            .map(|x| unsafe { f32::from_le_bytes(*(x as *const _ as *const [_; 4])) })
-            .unwrap();
-
-        Ok(s)
+        self.reader().get_f32(index)
     }
 
     pub fn get_f64(&mut self, index: usize) -> Result<f64, std::io::Error> {
-        self.index_out_of_bounds_check(index, 8, types::F64)?;
-
-        let start = self.raw_buffer.field_pos_index[index];
-        let s = self
-            .raw_buffer
-            .buf
-            .get(start..start + 8)
-            .map(|x| unsafe { f64::from_le_bytes(*(x as *const _ as *const [_; 8])) })
-            .unwrap();
-
-        Ok(s)
+        self.reader().get_f64(index)
     }
 
     pub fn get_str(&mut self, index: usize) -> Result<String, std::io::Error> {
-        match self.get_bytes(index) {
-            Ok(bytes) => String::from_utf8(bytes.to_vec())
-                .map_err(|_e| std::io::Error::from(ErrorKind::InvalidData)),
-            Err(e) => Err(e),
-        }
+        self.reader().get_str(index).map(|s| s.to_string())
     }
 
     pub fn get_bytes(&mut self, index: usize) -> Result<&[u8], std::io::Error> {
-        let start = self.raw_buffer.field_pos_index[index];
-        let s = self
-            .raw_buffer
-            .buf
-            .get(start..start + 4)
-            .map(|x| unsafe { u32::from_le_bytes(*(x as *const _ as *const [_; 4])) })
-            .unwrap();
-
-        let len = s as usize;
-
-        self.index_out_of_bounds_check(index, len + 4, types::BYTES)?;
-
-        let start = start + 4;
-
-        let s = self.raw_buffer.buf.get(start..start + len).unwrap();
-        Ok(s)
+        self.reader().get_bytes(index)
     }
 
     pub fn get_bytes_mut(&mut self, index: usize) -> Result<&mut [u8], std::io::Error> {
-        let start = self.raw_buffer.field_pos_index[index];
-        let s = self
-            .raw_buffer
-            .buf
-            .get(start..start + 4)
-            .map(|x| unsafe { u32::from_le_bytes(*(x as *const _ as *const [_; 4])) })
-            .unwrap();
-
-        let len = s as usize;
-
-        self.index_out_of_bounds_check(index, len + 4, types::BYTES)?;
-
-        let start = start + 4;
+        if self.schema.data_types()[index] != types::BYTES {
+            return Err(std::io::Error::from(ErrorKind::InvalidData));
+        }
 
-        let s = self.raw_buffer.buf.get_mut(start..start + len).unwrap();
-        Ok(s)
+        let range = self.reader().field_range(index)?;
+        Ok(&mut self.raw_buffer.buf[range])
     }
 
     pub fn get_bytes_raw(&mut self, index: usize) -> Result<&[u8], std::io::Error> {
-        let data_type = self.data_types[index];
-        if data_type == types::BYTES {
-            self.get_bytes(index)
-        } else {
-            let len = types::len(data_type) as usize;
-            let start = self.raw_buffer.field_pos_index[index];
-
-            let s = self.raw_buffer.buf.get(start..start + len).unwrap();
-
-            Ok(s)
-        }
+        self.reader().get_bytes_raw(index)
     }
 
     pub fn get_bytes_raw_mut(&mut self, index: usize) -> Result<&mut [u8], std::io::Error> {
-        let data_type = self.data_types[index];
-        if data_type == types::BYTES {
-            self.get_bytes_mut(index)
-        } else {
-            let len = types::len(data_type) as usize;
-            let start = self.raw_buffer.field_pos_index[index];
+        let range = self.reader().field_range(index)?;
+        Ok(&mut self.raw_buffer.buf[range])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+
+    use crate::{types, Buffer, BufferReader, Schema};
+
+    const DATA_TYPES: [u8; 15] = [
+        types::BOOL,
+        types::I8,
+        types::U8,
+        types::I16,
+        types::U16,
+        types::I32,
+        types::U32,
+        types::I64,
+        types::U64,
+        types::F32,
+        types::F64,
+        types::BYTES,
+        types::BYTES,
+        types::BYTES,
+        types::BYTES,
+    ];
+
+    fn write_buffer(bytes0: &[u8], bytes1: &[u8]) -> Buffer {
+        let mut buffer = Buffer::new();
+        let mut writer = buffer.as_writer(&DATA_TYPES);
+
+        writer.set_bool(true).unwrap();
+        writer.set_i8(-8).unwrap();
+        writer.set_u8(8).unwrap();
+        writer.set_i16(-16).unwrap();
+        writer.set_u16(16).unwrap();
+        writer.set_i32(-32).unwrap();
+        writer.set_u32(32).unwrap();
+        writer.set_i64(-64).unwrap();
+        writer.set_u64(64).unwrap();
+        writer.set_f32(32.32).unwrap();
+        writer.set_f64(64.64).unwrap();
+        writer.set_str("").unwrap();
+        writer.set_str("serbuffer").unwrap();
+        writer.set_bytes(bytes0).unwrap();
+        writer.set_bytes(bytes1).unwrap();
+        writer.finish().unwrap();
+
+        buffer
+    }
+
+    #[test]
+    pub fn reader_consistency_test() {
+        // length prefix of 3 and 4 bytes
+        let bytes0 = vec![1u8; 300];
+        let bytes1 = vec![2u8; 70_000];
+
+        let buffer = write_buffer(bytes0.as_slice(), bytes1.as_slice());
+        let mut buffer_mut = Buffer::from(buffer.buf.clone());
+
+        let reader = buffer.as_reader(&DATA_TYPES);
+        let mut mut_reader = buffer_mut.as_reader_mut(&DATA_TYPES);
+
+        assert_eq!(reader.get_bool(0).unwrap(), mut_reader.get_bool(0).unwrap());
+        assert_eq!(reader.get_i8(1).unwrap(), mut_reader.get_i8(1).unwrap());
+        assert_eq!(reader.get_u8(2).unwrap(), mut_reader.get_u8(2).unwrap());
+        assert_eq!(reader.get_i16(3).unwrap(), mut_reader.get_i16(3).unwrap());
+        assert_eq!(reader.get_u16(4).unwrap(), mut_reader.get_u16(4).unwrap());
+        assert_eq!(reader.get_i32(5).unwrap(), mut_reader.get_i32(5).unwrap());
+        assert_eq!(reader.get_u32(6).unwrap(), mut_reader.get_u32(6).unwrap());
+        assert_eq!(reader.get_i64(7).unwrap(), mut_reader.get_i64(7).unwrap());
+        assert_eq!(reader.get_u64(8).unwrap(), mut_reader.get_u64(8).unwrap());
+        assert_eq!(reader.get_f32(9).unwrap(), mut_reader.get_f32(9).unwrap());
+        assert_eq!(reader.get_f64(10).unwrap(), mut_reader.get_f64(10).unwrap());
+
+        assert_eq!(reader.get_str(11).unwrap(), "");
+        assert_eq!(reader.get_str(12).unwrap(), "serbuffer");
+        assert_eq!(reader.get_bytes(13).unwrap(), bytes0.as_slice());
+        assert_eq!(reader.get_bytes(14).unwrap(), bytes1.as_slice());
+
+        for index in 11..15 {
+            assert_eq!(
+                reader.get_bytes(index).unwrap(),
+                mut_reader.get_bytes(index).unwrap()
+            );
+            assert_eq!(
+                reader.get_bytes(index).unwrap(),
+                mut_reader.get_bytes_mut(index).unwrap()
+            );
+        }
+        assert_eq!(
+            reader.get_str(12).unwrap(),
+            mut_reader.get_str(12).unwrap().as_str()
+        );
+
+        for index in 0..DATA_TYPES.len() {
+            assert_eq!(
+                reader.get_bytes_raw(index).unwrap(),
+                mut_reader.get_bytes_raw(index).unwrap()
+            );
+            assert_eq!(
+                reader.get_bytes_raw(index).unwrap(),
+                mut_reader.get_bytes_raw_mut(index).unwrap()
+            );
+        }
 
-            let s = self.raw_buffer.buf.get_mut(start..start + len).unwrap();
+        // type mismatch
+        assert!(reader.get_i8(0).is_err());
+        assert!(mut_reader.get_i8(0).is_err());
+        assert!(reader.get_bytes(0).is_err());
+        assert!(mut_reader.get_bytes(0).is_err());
+        assert!(mut_reader.get_bytes_mut(0).is_err());
+    }
+
+    #[test]
+    pub fn reader_from_slice_test() {
+        let buffer = write_buffer(&[1u8; 300], b"def");
+        let bytes = Bytes::copy_from_slice(buffer.as_slice());
+        let vec = buffer.as_slice().to_vec();
+
+        let reader = buffer.as_reader(&DATA_TYPES);
+        let slice_readers = [
+            BufferReader::from_slice(buffer.as_slice(), &DATA_TYPES),
+            BufferReader::from_slice(&bytes, &DATA_TYPES),
+            BufferReader::from_slice(&vec, &DATA_TYPES),
+        ];
+        for slice_reader in &slice_readers {
+            for index in 0..DATA_TYPES.len() {
+                assert_eq!(slice_reader.get(index).unwrap(), reader.get(index).unwrap());
+            }
+        }
 
-            Ok(s)
+        // fixed width fields by the named schema
+        let schema = Schema::named(&["id", "flag"], &[types::I32, types::BOOL]).unwrap();
+        let data = [7u8, 0, 0, 0, 1];
+        let reader = BufferReader::from_slice(&data, &schema);
+        assert_eq!(reader.get_i32_by_name("id").unwrap(), 7);
+        assert!(reader.get_bool_by_name("flag").unwrap());
+
+        // the corrupt records fail the first field access
+        assert!(BufferReader::from_slice(&data[..4], &schema)
+            .get_i32(0)
+            .is_err());
+        assert!(BufferReader::from_slice(&vec[..vec.len() - 1], &DATA_TYPES)
+            .get(0)
+            .is_err());
+
+        let corrupt = [0xFE, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0];
+        let reader = BufferReader::from_slice(&corrupt, &[types::BYTES, types::BOOL]);
+        assert!(reader.get_bool(1).is_err());
+        assert!(reader.iter().all(|value| value.is_err()));
+    }
+
+    #[test]
+    pub fn reader_corrupt_field_test() {
+        // i64 fields read back as bytes fields with a huge length prefix
+        let mut buffer = Buffer::new();
+        let mut writer = buffer.as_writer(&[types::I64, types::I64]);
+        writer.set_i64(-2).unwrap();
+        writer.set_i64(-1).unwrap();
+        writer.finish().unwrap();
+
+        let reader = BufferReader::from_slice(buffer.as_slice(), &[types::BYTES, types::BYTES]);
+        assert!(reader.get_bytes(0).is_err());
+        assert!(reader.get_bytes_raw(0).is_err());
+        assert!(reader.get(0).is_err());
+
+        // the field index is out of range of the empty buffer
+        let reader = BufferReader::from_slice(&[], &[]);
+        assert_eq!(
+            reader.get_i32(0).unwrap_err().kind(),
+            std::io::ErrorKind::InvalidInput
+        );
+        assert!(reader.get_bytes(0).is_err());
+        assert!(reader.get_bytes_raw(0).is_err());
+    }
+
+    #[test]
+    pub fn reader_mut_write_through_test() {
+        let mut buffer = write_buffer(b"abc", b"def");
+
+        {
+            let mut mut_reader = buffer.as_reader_mut(&DATA_TYPES);
+            mut_reader
+                .get_bytes_mut(13)
+                .unwrap()
+                .copy_from_slice(b"xyz");
+            mut_reader.get_bytes_raw_mut(8).unwrap()[0] = 65;
         }
+
+        let reader = buffer.as_reader(&DATA_TYPES);
+        assert_eq!(reader.get_bytes(13).unwrap(), b"xyz");
+        assert_eq!(reader.get_bytes(14).unwrap(), b"def");
+        assert_eq!(reader.get_u64(8).unwrap(), 65);
     }
 }
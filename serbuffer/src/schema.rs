@@ -0,0 +1,365 @@
+use std::collections::{HashMap, HashSet};
+use std::io::ErrorKind;
+
+use crate::types;
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+/// validated data types of a record, with optional field names.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Schema {
+    data_types: Vec<u8>,
+    field_names: Option<Vec<String>>,
+    /// field name to field index
+    field_indices: HashMap<String, usize>,
+    /// the start position of each field, `None` after the first `BYTES` field.
+    fixed_offsets: Vec<Option<usize>>,
+    fixed_size: usize,
+    fingerprint: u64,
+}
+
+impl Schema {
+    pub fn new(data_types: &[u8]) -> Result<Self, std::io::Error> {
+        Schema::build(data_types, None)
+    }
+
+    pub fn named(field_names: &[&str], data_types: &[u8]) -> Result<Self, std::io::Error> {
+        if field_names.len() != data_types.len() {
+            return Err(std::io::Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "{} field names for {} data types",
+                    field_names.len(),
+                    data_types.len()
+                ),
+            ));
+        }
+
+        let mut name_set = HashSet::new();
+        for field_name in field_names {
+            if !name_set.insert(*field_name) {
+                return Err(std::io::Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("duplicate field name `{}`", field_name),
+                ));
+            }
+        }
+
+        let field_names = field_names.iter().map(|name| name.to_string()).collect();
+        Schema::build(data_types, Some(field_names))
+    }
+
+    fn build(data_types: &[u8], field_names: Option<Vec<String>>) -> Result<Self, std::io::Error> {
+        let mut fixed_offsets = Vec::with_capacity(data_types.len());
+        let mut offset = Some(0);
+        for (index, data_type) in data_types.iter().enumerate() {
+            if !types::is_valid(*data_type) {
+                return Err(std::io::Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("invalid data type {:#010b} of field {}", data_type, index),
+                ));
+            }
+
+            fixed_offsets.push(offset);
+            offset = match offset {
+                Some(offset) if *data_type != types::BYTES => {
+                    Some(offset + types::len(*data_type) as usize)
+                }
+                _ => None,
+            };
+        }
+
+        let fixed_size = data_types
+            .iter()
+            .filter(|data_type| **data_type != types::BYTES)
+            .map(|data_type| types::len(*data_type) as usize)
+            .sum();
+        let fingerprint = fingerprint(data_types, field_names.as_deref());
+        let field_indices = field_names
+            .iter()
+            .flatten()
+            .enumerate()
+            .map(|(index, name)| (name.clone(), index))
+            .collect();
+
+        Ok(Schema {
+            data_types: data_types.to_vec(),
+            field_names,
+            field_indices,
+            fixed_offsets,
+            fixed_size,
+            fingerprint,
+        })
+    }
+
+    pub fn data_types(&self) -> &[u8] {
+        self.data_types.as_slice()
+    }
+
+    pub fn len(&self) -> usize {
+        self.data_types.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data_types.is_empty()
+    }
+
+    pub fn field_names(&self) -> Option<&[String]> {
+        self.field_names.as_deref()
+    }
+
+    pub fn field_name(&self, index: usize) -> Option<&str> {
+        self.field_names
+            .as_ref()
+            .and_then(|names| names.get(index))
+            .map(|name| name.as_str())
+    }
+
+    /// the index of the field named `name`.
+    pub fn index_of(&self, name: &str) -> Option<usize> {
+        self.field_indices.get(name).cloned()
+    }
+
+    /// the start position of the field, known only if all previous fields are fixed width.
+    pub fn fixed_offset(&self, index: usize) -> Option<usize> {
+        self.fixed_offsets.get(index).cloned().flatten()
+    }
+
+    /// the total size of the fixed width fields, the `BYTES` fields are not included.
+    /// the exact record size is `fixed_size` plus `lenenc_int_len(len) + len` of each `BYTES`
+    /// field, unlike `types::min_encoded_size` which is only a capacity hint.
+    pub fn fixed_size(&self) -> usize {
+        self.fixed_size
+    }
+
+    /// all fields are fixed width, so the field positions are the same for every record.
+    pub fn is_fixed(&self) -> bool {
+        !self.data_types.contains(&types::BYTES)
+    }
+
+    /// stable FNV-1a hash of the data types and the field names.
+    pub fn fingerprint(&self) -> u64 {
+        self.fingerprint
+    }
+}
+
+impl std::hash::Hash for Schema {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.fingerprint.hash(state)
+    }
+}
+
+impl AsRef<[u8]> for Schema {
+    fn as_ref(&self) -> &[u8] {
+        self.data_types()
+    }
+}
+
+/// the schema text format, one field per line as `name: TYPE`, or only `TYPE` for unnamed
+/// schema. empty lines and `#` comments are ignored.
+/// eg:
+/// # order record
+/// id: I64
+/// name: STRING
+impl std::str::FromStr for Schema {
+    type Err = std::io::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut field_names = Vec::new();
+        let mut data_types = Vec::new();
+        for (line_index, line) in s.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            let (field_name, type_name) = match line.split_once(':') {
+                Some((field_name, type_name)) => (Some(field_name.trim()), type_name.trim()),
+                None => (None, line),
+            };
+            let data_type = types::from_name(type_name).ok_or_else(|| {
+                std::io::Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("line {}: unknown type `{}`", line_index + 1, type_name),
+                )
+            })?;
+
+            if !data_types.is_empty() && field_name.is_some() == field_names.is_empty() {
+                return Err(std::io::Error::new(
+                    ErrorKind::InvalidInput,
+                    format!(
+                        "line {}: all or none of the fields are named",
+                        line_index + 1
+                    ),
+                ));
+            }
+            field_names.extend(field_name);
+            data_types.push(data_type);
+        }
+
+        if field_names.is_empty() {
+            Schema::new(data_types.as_slice())
+        } else {
+            Schema::named(field_names.as_slice(), data_types.as_slice())
+        }
+    }
+}
+
+/// print in the schema text format, see `FromStr`.
+impl std::fmt::Display for Schema {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (index, data_type) in self.data_types.iter().enumerate() {
+            match self.field_name(index) {
+                Some(field_name) => writeln!(f, "{}: {}", field_name, types::name(*data_type))?,
+                None => writeln!(f, "{}", types::name(*data_type))?,
+            }
+        }
+        Ok(())
+    }
+}
+
+fn fingerprint(data_types: &[u8], field_names: Option<&[String]>) -> u64 {
+    let mut hash = FNV_OFFSET_BASIS;
+    let mut write = |bytes: &[u8]| {
+        for b in bytes {
+            hash ^= *b as u64;
+            hash = hash.wrapping_mul(FNV_PRIME);
+        }
+    };
+
+    write(data_types);
+    if let Some(field_names) = field_names {
+        for field_name in field_names {
+            write(&[0]);
+            write(field_name.as_bytes());
+        }
+    }
+
+    hash
+}
+
+/// borrowed data types, and the `Schema` if they come from one.
+/// the `as_reader`/`as_writer` accept `&Schema` and the raw `&[u8]` data types.
+#[derive(Clone, Copy, Debug)]
+pub struct SchemaRef<'a> {
+    data_types: &'a [u8],
+    schema: Option<&'a Schema>,
+}
+
+impl<'a> SchemaRef<'a> {
+    pub fn data_types(&self) -> &'a [u8] {
+        self.data_types
+    }
+
+    pub fn schema(&self) -> Option<&'a Schema> {
+        self.schema
+    }
+}
+
+impl<'a> From<&'a Schema> for SchemaRef<'a> {
+    fn from(schema: &'a Schema) -> Self {
+        SchemaRef {
+            data_types: schema.data_types(),
+            schema: Some(schema),
+        }
+    }
+}
+
+impl<'a> From<&'a [u8]> for SchemaRef<'a> {
+    fn from(data_types: &'a [u8]) -> Self {
+        SchemaRef {
+            data_types,
+            schema: None,
+        }
+    }
+}
+
+impl<'a, const N: usize> From<&'a [u8; N]> for SchemaRef<'a> {
+    fn from(data_types: &'a [u8; N]) -> Self {
+        SchemaRef::from(&data_types[..])
+    }
+}
+
+impl<'a> From<&'a Vec<u8>> for SchemaRef<'a> {
+    fn from(data_types: &'a Vec<u8>) -> Self {
+        SchemaRef::from(data_types.as_slice())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::schema::Schema;
+    use crate::{types, Buffer};
+
+    #[test]
+    pub fn schema_test() {
+        let data_types = [types::I32, types::U8, types::BYTES, types::I64];
+
+        let schema = Schema::new(&data_types).unwrap();
+        assert_eq!(schema.data_types(), &data_types);
+        assert_eq!(schema.fixed_offset(0), Some(0));
+        assert_eq!(schema.fixed_offset(1), Some(4));
+        assert_eq!(schema.fixed_offset(2), Some(5));
+        assert_eq!(schema.fixed_offset(3), None);
+        assert!(!schema.is_fixed());
+        assert_eq!(schema.fixed_size(), 4 + 1 + 8);
+        assert!(schema.field_names().is_none());
+
+        let named = Schema::named(&["a", "b", "c", "d"], &data_types).unwrap();
+        assert_eq!(named.field_name(2), Some("c"));
+        assert_eq!(named.index_of("c"), Some(2));
+        assert_eq!(named.index_of("e"), None);
+        assert_eq!(schema.index_of("c"), None);
+        assert_ne!(named.fingerprint(), schema.fingerprint());
+        assert_eq!(
+            named.fingerprint(),
+            Schema::named(&["a", "b", "c", "d"], &data_types)
+                .unwrap()
+                .fingerprint()
+        );
+
+        assert!(Schema::new(&[types::I32, 0b0110_0000]).is_err());
+        assert!(Schema::named(&["a", "a"], &[types::I32, types::I32]).is_err());
+        assert!(Schema::named(&["a"], &[types::I32, types::I32]).is_err());
+
+        let mut buffer = Buffer::new();
+        {
+            let mut writer = buffer.as_writer(&schema);
+            writer.set_i32(1).unwrap();
+            writer.set_u8(2).unwrap();
+            writer.set_str("abc").unwrap();
+            writer.set_i64(4).unwrap();
+            writer.finish().unwrap();
+        }
+
+        let buffer = Buffer::from(buffer.buf.clone());
+        let reader = buffer.as_reader(&schema);
+        assert_eq!(reader.get_u8(1).unwrap(), 2);
+        assert_eq!(reader.get_str(2).unwrap(), "abc");
+        assert_eq!(reader.get_i64(3).unwrap(), 4);
+
+        // text format
+        let text = "# test\na: I32\nb: u8\n\nc: STRING # name\nd: I64\n";
+        assert_eq!(text.parse::<Schema>().unwrap(), named);
+        assert_eq!(named.to_string().parse::<Schema>().unwrap(), named);
+        assert_eq!(schema.to_string().parse::<Schema>().unwrap(), schema);
+        assert!("a: I32\nb: X".parse::<Schema>().is_err());
+        assert!("a: I32\nI32".parse::<Schema>().is_err());
+
+        // fixed width schema
+        let schema = Schema::new(&[types::I32, types::U8]).unwrap();
+        assert!(schema.is_fixed());
+        let mut buffer = Buffer::new();
+        {
+            let mut writer = buffer.as_writer(&schema);
+            writer.set_i32(1).unwrap();
+            writer.set_u8(2).unwrap();
+            writer.finish().unwrap();
+        }
+        let buffer = Buffer::from(buffer.buf.clone());
+        let reader = buffer.as_reader(&schema);
+        assert_eq!(reader.get_i32(0).unwrap(), 1);
+        assert_eq!(reader.get_u8(1).unwrap(), 2);
+    }
+}
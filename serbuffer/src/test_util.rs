@@ -0,0 +1,44 @@
+//! the record fixtures of the unit tests, an `I64` id and a `STRING` name.
+
+use std::io::Write;
+use std::ops::Range;
+
+use crate::file::FileWriterBuilder;
+use crate::{types, Buffer, Schema};
+
+pub(crate) fn schema() -> Schema {
+    Schema::named(&["id", "name"], &[types::I64, types::STRING]).unwrap()
+}
+
+pub(crate) fn name(id: i64) -> String {
+    format!("name-{}", id)
+}
+
+pub(crate) fn record_named(schema: &Schema, id: i64, name: &str) -> Buffer {
+    let mut buffer = Buffer::new();
+    let mut writer = buffer.as_writer(schema);
+    writer.set_i64(id).unwrap();
+    writer.set_str(name).unwrap();
+    writer.finish().unwrap();
+    buffer
+}
+
+pub(crate) fn record(schema: &Schema, id: i64) -> Buffer {
+    record_named(schema, id, name(id).as_str())
+}
+
+pub(crate) fn records(schema: &Schema, ids: Range<i64>) -> Vec<Buffer> {
+    ids.map(|id| record(schema, id)).collect()
+}
+
+/// a record file of the records `0..n`, in blocks of about 100 bytes.
+pub(crate) fn write_file<W: Write>(schema: &Schema, n: i64, writer: W) -> W {
+    let mut writer = FileWriterBuilder::new(schema)
+        .block_size(100)
+        .build(writer)
+        .unwrap();
+    for buffer in records(schema, 0..n) {
+        writer.write(&buffer).unwrap();
+    }
+    writer.finish().unwrap()
+}
@@ -0,0 +1,60 @@
+use crate::types;
+
+/// dynamic field value, borrow the `BYTES` data from the buffer.
+/// `STRING` and `BYTES` share the same type, so readers always return `Bytes`,
+/// `Str` is accepted by writers.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Value<'a> {
+    Bool(bool),
+    I8(i8),
+    U8(u8),
+    I16(i16),
+    U16(u16),
+    I32(i32),
+    U32(u32),
+    I64(i64),
+    U64(u64),
+    F32(f32),
+    F64(f64),
+    Bytes(&'a [u8]),
+    Str(&'a str),
+}
+
+impl<'a> Value<'a> {
+    /// the `types::*` of the value.
+    pub fn data_type(&self) -> u8 {
+        match self {
+            Value::Bool(_) => types::BOOL,
+            Value::I8(_) => types::I8,
+            Value::U8(_) => types::U8,
+            Value::I16(_) => types::I16,
+            Value::U16(_) => types::U16,
+            Value::I32(_) => types::I32,
+            Value::U32(_) => types::U32,
+            Value::I64(_) => types::I64,
+            Value::U64(_) => types::U64,
+            Value::F32(_) => types::F32,
+            Value::F64(_) => types::F64,
+            Value::Bytes(_) => types::BYTES,
+            Value::Str(_) => types::STRING,
+        }
+    }
+
+    /// the `Str` value, or the `Bytes` value if it is valid UTF-8.
+    pub fn as_str(&self) -> Option<&'a str> {
+        match self {
+            Value::Str(s) => Some(s),
+            Value::Bytes(b) => std::str::from_utf8(b).ok(),
+            _ => None,
+        }
+    }
+
+    /// the `Bytes` value, or the bytes of the `Str` value.
+    pub fn as_bytes(&self) -> Option<&'a [u8]> {
+        match self {
+            Value::Str(s) => Some(s.as_bytes()),
+            Value::Bytes(b) => Some(b),
+            _ => None,
+        }
+    }
+}
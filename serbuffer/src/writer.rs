@@ -1,42 +1,153 @@
 use std::io::ErrorKind;
+use std::ops::{Deref, DerefMut, Range};
 
 use bytes::BufMut;
 
-use crate::encoding::write_lenenc_int;
+use crate::encoding::{lenenc_int_len, write_lenenc_int};
+use crate::schema::SchemaRef;
+use crate::value::Value;
 use crate::{types, Buffer};
-use std::borrow::BorrowMut;
 
 pub struct BufferWriter<'a, 'b> {
     raw_buffer: &'a mut Buffer,
     data_types: &'b [u8],
     write_field_step: usize,
     // write_position: usize,
+    /// the `buf_len`, `field_pos_index` length and `write_field_step` before writing,
+    /// `Some` in transactional mode.
+    rollback_state: Option<(usize, usize, usize)>,
+    finish_check: FinishCheck,
+}
+
+/// warn in the debug build if the writer is dropped after writing some but not all fields
+/// without `finish`. it is a field instead of a `Drop` of `BufferWriter`, so the borrow of the
+/// buffer still end at the last use of the writer.
+#[cfg_attr(not(debug_assertions), allow(dead_code))]
+struct FinishCheck {
+    written: usize,
+    fields: usize,
+    finished: bool,
+}
+
+#[cfg_attr(not(debug_assertions), allow(dead_code))]
+impl FinishCheck {
+    fn warning(&self) -> Option<String> {
+        if self.finished
+            || self.written == 0
+            || self.written >= self.fields
+            || std::thread::panicking()
+        {
+            return None;
+        }
+
+        Some(format!(
+            "serbuffer: `BufferWriter` dropped after writing {} of {} fields without `finish`",
+            self.written, self.fields
+        ))
+    }
+}
+
+#[cfg(debug_assertions)]
+impl Drop for FinishCheck {
+    fn drop(&mut self) {
+        if let Some(warning) = self.warning() {
+            eprintln!("{}", warning);
+        }
+    }
 }
 
 impl<'a, 'b> BufferWriter<'a, 'b> {
     pub(crate) fn new(raw_buffer: &'a mut Buffer, data_types: &'b [u8]) -> Self {
+        BufferWriter::new_at(raw_buffer, data_types, 0)
+    }
+
+    /// the writer start at field `write_field_step`, the previous fields are already written.
+    pub(crate) fn new_at(
+        raw_buffer: &'a mut Buffer,
+        data_types: &'b [u8],
+        write_field_step: usize,
+    ) -> Self {
+        // an empty buffer start a new field position index
+        if raw_buffer.buf_len == 0 {
+            raw_buffer.field_pos_index_mut().clear();
+        }
+
         BufferWriter {
             raw_buffer,
             data_types,
-            write_field_step: 0,
+            write_field_step,
+            rollback_state: None,
+            finish_check: FinishCheck {
+                written: write_field_step,
+                fields: data_types.len(),
+                finished: false,
+            },
         }
     }
 
     #[inline]
     fn data_type_check(&mut self, data_type: u8) -> Result<(), std::io::Error> {
-        if self.data_types[self.write_field_step] != data_type {
+        if self.data_types.get(self.write_field_step) != Some(&data_type) {
+            self.rollback();
             return Err(std::io::Error::from(ErrorKind::InvalidInput));
         }
 
+        self.step_field();
+        Ok(())
+    }
+
+    #[inline]
+    fn step_field(&mut self) {
         self.write_field_step += 1;
+        self.finish_check.written = self.write_field_step;
+    }
+
+    fn rollback(&mut self) {
+        if let Some((buf_len, field_pos_index_len, write_field_step)) = self.rollback_state {
+            self.raw_buffer.buf.truncate(buf_len);
+            self.raw_buffer.buf_len = buf_len;
+            if let Some(field_pos_index) = self.raw_buffer.field_pos_index.get_mut() {
+                field_pos_index.truncate(field_pos_index_len);
+            }
+            self.write_field_step = write_field_step;
+            self.finish_check.written = write_field_step;
+        }
+    }
+
+    /// check all fields are written, and end the writing.
+    pub fn finish(mut self) -> Result<(), std::io::Error> {
+        self.finish_check.finished = true;
+        self.check_finished()
+    }
+
+    /// the warning of the debug build if the writer is dropped now.
+    #[cfg(test)]
+    pub(crate) fn drop_warning(&self) -> Option<String> {
+        self.finish_check.warning()
+    }
+
+    fn check_finished(&mut self) -> Result<(), std::io::Error> {
+        if self.write_field_step != self.data_types.len() {
+            self.rollback();
+            return Err(std::io::Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "{} of {} fields are written",
+                    self.write_field_step,
+                    self.data_types.len()
+                ),
+            ));
+        }
+
         Ok(())
     }
 
     #[inline]
     fn step_position(&mut self, pos_step_len: usize) {
-        self.raw_buffer
-            .field_pos_index
-            .push(self.raw_buffer.buf_len);
+        let buf_len = self.raw_buffer.buf_len;
+        if let Some(field_pos_index) = self.raw_buffer.field_pos_index.get_mut() {
+            field_pos_index.push(buf_len);
+        }
         self.raw_buffer.buf_len += pos_step_len;
     }
 
@@ -151,7 +262,7 @@ impl<'a, 'b> BufferWriter<'a, 'b> {
 
         let len = value.len();
 
-        let len_length = write_lenenc_int(len as u64, self.raw_buffer.buf.borrow_mut());
+        let len_length = write_lenenc_int(len as u64, &mut self.raw_buffer.buf);
         self.raw_buffer.buf.put_slice(value);
 
         self.step_position(len + len_length);
@@ -171,16 +282,23 @@ impl<'a, 'b> BufferWriter<'a, 'b> {
     // }
 
     pub fn set_bytes_raw(&mut self, value: &[u8]) -> Result<(), std::io::Error> {
-        let data_type = self.data_types[self.write_field_step];
+        let data_type = match self.data_types.get(self.write_field_step) {
+            Some(data_type) => *data_type,
+            None => {
+                self.rollback();
+                return Err(std::io::Error::from(ErrorKind::InvalidInput));
+            }
+        };
         if data_type == types::BYTES {
             self.set_bytes(value)
         } else {
             let len = types::len(data_type) as usize;
             if len != value.len() {
+                self.rollback();
                 return Err(std::io::Error::from(ErrorKind::InvalidInput));
             }
 
-            self.write_field_step += 1;
+            self.step_field();
 
             self.step_position(len);
 
@@ -189,3 +307,376 @@ impl<'a, 'b> BufferWriter<'a, 'b> {
         }
     }
 }
+
+impl<'a, 'b> BufferWriter<'a, 'b> {
+    /// write the next field, the value type must match the data type of the field.
+    pub fn set(&mut self, value: Value) -> Result<(), std::io::Error> {
+        match value {
+            Value::Bool(v) => self.set_bool(v),
+            Value::I8(v) => self.set_i8(v),
+            Value::U8(v) => self.set_u8(v),
+            Value::I16(v) => self.set_i16(v),
+            Value::U16(v) => self.set_u16(v),
+            Value::I32(v) => self.set_i32(v),
+            Value::U32(v) => self.set_u32(v),
+            Value::I64(v) => self.set_i64(v),
+            Value::U64(v) => self.set_u64(v),
+            Value::F32(v) => self.set_f32(v),
+            Value::F64(v) => self.set_f64(v),
+            Value::Bytes(v) => self.set_bytes(v),
+            Value::Str(v) => self.set_str(v),
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// `BufferWriter` roll back the written fields if it is dropped without `finish` or any `set_*`
+/// fail, the buffer is left as before the writing.
+pub struct TransactionalBufferWriter<'a, 'b> {
+    writer: BufferWriter<'a, 'b>,
+    finished: bool,
+}
+
+impl<'a, 'b> TransactionalBufferWriter<'a, 'b> {
+    pub(crate) fn new(raw_buffer: &'a mut Buffer, data_types: &'b [u8]) -> Self {
+        let mut writer = BufferWriter::new(raw_buffer, data_types);
+        writer.rollback_state = Some((
+            writer.raw_buffer.buf_len,
+            writer.raw_buffer.field_pos_index().len(),
+            writer.write_field_step,
+        ));
+
+        TransactionalBufferWriter {
+            writer,
+            finished: false,
+        }
+    }
+
+    /// check all fields are written and end the writing, roll back if not.
+    pub fn finish(mut self) -> Result<(), std::io::Error> {
+        self.finished = true;
+        self.writer.check_finished()
+    }
+}
+
+impl<'a, 'b> Deref for TransactionalBufferWriter<'a, 'b> {
+    type Target = BufferWriter<'a, 'b>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.writer
+    }
+}
+
+impl<'a, 'b> DerefMut for TransactionalBufferWriter<'a, 'b> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.writer
+    }
+}
+
+impl<'a, 'b> Drop for TransactionalBufferWriter<'a, 'b> {
+    fn drop(&mut self) {
+        if !self.finished {
+            self.writer.rollback();
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// writer accept the fields by index in any order.
+/// the values are staged until `finish`, then written to the buffer in schema order.
+pub struct UnorderedBufferWriter<'a, 'b> {
+    raw_buffer: &'a mut Buffer,
+    data_types: &'b [u8],
+    /// staged field values, the length prefix of `BYTES` is not included.
+    staging: Vec<u8>,
+    staged_fields: Vec<Option<Range<usize>>>,
+}
+
+impl<'a, 'b> UnorderedBufferWriter<'a, 'b> {
+    pub(crate) fn new(raw_buffer: &'a mut Buffer, data_types: &'b [u8]) -> Self {
+        UnorderedBufferWriter {
+            raw_buffer,
+            data_types,
+            staging: Vec::new(),
+            staged_fields: vec![None; data_types.len()],
+        }
+    }
+
+    #[inline]
+    fn stage(&mut self, index: usize, data_type: u8, value: &[u8]) -> Result<(), std::io::Error> {
+        match self.data_types.get(index) {
+            Some(dt) if *dt == data_type => {}
+            _ => return Err(std::io::Error::from(ErrorKind::InvalidInput)),
+        }
+
+        let start = self.staging.len();
+        self.staging.extend_from_slice(value);
+        self.staged_fields[index] = Some(start..self.staging.len());
+
+        Ok(())
+    }
+
+    pub fn set_bool(&mut self, index: usize, value: bool) -> Result<(), std::io::Error> {
+        let value = if value { 1 } else { 0 };
+        self.stage(index, types::BOOL, &[value])
+    }
+
+    pub fn set_i8(&mut self, index: usize, value: i8) -> Result<(), std::io::Error> {
+        self.stage(index, types::I8, &value.to_le_bytes())
+    }
+
+    pub fn set_u8(&mut self, index: usize, value: u8) -> Result<(), std::io::Error> {
+        self.stage(index, types::U8, &value.to_le_bytes())
+    }
+
+    pub fn set_i16(&mut self, index: usize, value: i16) -> Result<(), std::io::Error> {
+        self.stage(index, types::I16, &value.to_le_bytes())
+    }
+
+    pub fn set_u16(&mut self, index: usize, value: u16) -> Result<(), std::io::Error> {
+        self.stage(index, types::U16, &value.to_le_bytes())
+    }
+
+    pub fn set_i32(&mut self, index: usize, value: i32) -> Result<(), std::io::Error> {
+        self.stage(index, types::I32, &value.to_le_bytes())
+    }
+
+    pub fn set_u32(&mut self, index: usize, value: u32) -> Result<(), std::io::Error> {
+        self.stage(index, types::U32, &value.to_le_bytes())
+    }
+
+    pub fn set_i64(&mut self, index: usize, value: i64) -> Result<(), std::io::Error> {
+        self.stage(index, types::I64, &value.to_le_bytes())
+    }
+
+    pub fn set_u64(&mut self, index: usize, value: u64) -> Result<(), std::io::Error> {
+        self.stage(index, types::U64, &value.to_le_bytes())
+    }
+
+    pub fn set_f32(&mut self, index: usize, value: f32) -> Result<(), std::io::Error> {
+        self.stage(index, types::F32, &value.to_le_bytes())
+    }
+
+    pub fn set_f64(&mut self, index: usize, value: f64) -> Result<(), std::io::Error> {
+        self.stage(index, types::F64, &value.to_le_bytes())
+    }
+
+    pub fn set_str(&mut self, index: usize, value: &str) -> Result<(), std::io::Error> {
+        self.set_bytes(index, value.as_bytes())
+    }
+
+    pub fn set_bytes(&mut self, index: usize, value: &[u8]) -> Result<(), std::io::Error> {
+        self.stage(index, types::BYTES, value)
+    }
+
+    /// stage the field `index`, the value type must match the data type of the field.
+    pub fn set(&mut self, index: usize, value: Value) -> Result<(), std::io::Error> {
+        match value {
+            Value::Bool(v) => self.set_bool(index, v),
+            Value::I8(v) => self.set_i8(index, v),
+            Value::U8(v) => self.set_u8(index, v),
+            Value::I16(v) => self.set_i16(index, v),
+            Value::U16(v) => self.set_u16(index, v),
+            Value::I32(v) => self.set_i32(index, v),
+            Value::U32(v) => self.set_u32(index, v),
+            Value::I64(v) => self.set_i64(index, v),
+            Value::U64(v) => self.set_u64(index, v),
+            Value::F32(v) => self.set_f32(index, v),
+            Value::F64(v) => self.set_f64(index, v),
+            Value::Bytes(v) => self.set_bytes(index, v),
+            Value::Str(v) => self.set_str(index, v),
+        }
+    }
+
+    /// write the staged fields to the buffer in schema order.
+    /// all fields are non-nullable, fail with the unset field indices if any field is missing,
+    /// and the buffer is not changed.
+    pub fn finish(self) -> Result<(), std::io::Error> {
+        let unset_fields: Vec<usize> = self
+            .staged_fields
+            .iter()
+            .enumerate()
+            .filter(|(_, field)| field.is_none())
+            .map(|(index, _)| index)
+            .collect();
+        if !unset_fields.is_empty() {
+            return Err(std::io::Error::new(
+                ErrorKind::InvalidInput,
+                format!("fields {:?} are not set", unset_fields),
+            ));
+        }
+
+        let mut writer = BufferWriter::new(self.raw_buffer, self.data_types);
+        for field in self.staged_fields.into_iter().flatten() {
+            writer.set_bytes_raw(&self.staging[field])?;
+        }
+
+        writer.finish()
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// writer of one record into a caller provided `BufMut`, such as an outgoing network buffer or
+/// the shared arena of a batch, no intermediate `Buffer` is needed.
+/// the data types are checked before a field is written, but the written fields are not rolled
+/// back if `finish` fail, the record in the storage must be discarded by the caller.
+pub struct BufMutWriter<'a, 'b, B: BufMut + ?Sized> {
+    buf: &'a mut B,
+    data_types: &'b [u8],
+    /// the written length
+    len: usize,
+    field_pos_index: Vec<usize>,
+}
+
+impl<'a, 'b, B: BufMut + ?Sized> BufMutWriter<'a, 'b, B> {
+    pub fn new(buf: &'a mut B, schema: impl Into<SchemaRef<'b>>) -> Self {
+        let data_types = schema.into().data_types();
+        BufMutWriter {
+            buf,
+            data_types,
+            len: 0,
+            field_pos_index: Vec::with_capacity(data_types.len()),
+        }
+    }
+
+    /// check the data type of the next field and the remaining space of the storage,
+    /// then step to the next field.
+    #[inline]
+    fn step(&mut self, data_type: u8, field_len: usize) -> Result<(), std::io::Error> {
+        if self.data_types.get(self.field_pos_index.len()) != Some(&data_type) {
+            return Err(std::io::Error::from(ErrorKind::InvalidInput));
+        }
+
+        if self.buf.remaining_mut() < field_len {
+            return Err(std::io::Error::new(
+                ErrorKind::WriteZero,
+                format!(
+                    "field {} of {} bytes, {} bytes remaining",
+                    self.field_pos_index.len(),
+                    field_len,
+                    self.buf.remaining_mut()
+                ),
+            ));
+        }
+
+        self.field_pos_index.push(self.len);
+        self.len += field_len;
+        Ok(())
+    }
+
+    /// check all fields are written, return the written length and the field offsets.
+    pub fn finish(self) -> Result<(usize, Vec<usize>), std::io::Error> {
+        if self.field_pos_index.len() != self.data_types.len() {
+            return Err(std::io::Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "{} of {} fields are written",
+                    self.field_pos_index.len(),
+                    self.data_types.len()
+                ),
+            ));
+        }
+
+        Ok((self.len, self.field_pos_index))
+    }
+
+    pub fn set_bool(&mut self, value: bool) -> Result<(), std::io::Error> {
+        self.step(types::BOOL, 1)?;
+        self.buf.put_u8(if value { 1 } else { 0 });
+        Ok(())
+    }
+
+    pub fn set_i8(&mut self, value: i8) -> Result<(), std::io::Error> {
+        self.step(types::I8, 1)?;
+        self.buf.put_i8(value);
+        Ok(())
+    }
+
+    pub fn set_u8(&mut self, value: u8) -> Result<(), std::io::Error> {
+        self.step(types::U8, 1)?;
+        self.buf.put_u8(value);
+        Ok(())
+    }
+
+    pub fn set_i16(&mut self, value: i16) -> Result<(), std::io::Error> {
+        self.step(types::I16, 2)?;
+        self.buf.put_i16_le(value);
+        Ok(())
+    }
+
+    pub fn set_u16(&mut self, value: u16) -> Result<(), std::io::Error> {
+        self.step(types::U16, 2)?;
+        self.buf.put_u16_le(value);
+        Ok(())
+    }
+
+    pub fn set_i32(&mut self, value: i32) -> Result<(), std::io::Error> {
+        self.step(types::I32, 4)?;
+        self.buf.put_i32_le(value);
+        Ok(())
+    }
+
+    pub fn set_u32(&mut self, value: u32) -> Result<(), std::io::Error> {
+        self.step(types::U32, 4)?;
+        self.buf.put_u32_le(value);
+        Ok(())
+    }
+
+    pub fn set_i64(&mut self, value: i64) -> Result<(), std::io::Error> {
+        self.step(types::I64, 8)?;
+        self.buf.put_i64_le(value);
+        Ok(())
+    }
+
+    pub fn set_u64(&mut self, value: u64) -> Result<(), std::io::Error> {
+        self.step(types::U64, 8)?;
+        self.buf.put_u64_le(value);
+        Ok(())
+    }
+
+    pub fn set_f32(&mut self, value: f32) -> Result<(), std::io::Error> {
+        self.step(types::F32, 4)?;
+        self.buf.put_f32_le(value);
+        Ok(())
+    }
+
+    pub fn set_f64(&mut self, value: f64) -> Result<(), std::io::Error> {
+        self.step(types::F64, 8)?;
+        self.buf.put_f64_le(value);
+        Ok(())
+    }
+
+    pub fn set_str(&mut self, value: &str) -> Result<(), std::io::Error> {
+        self.set_bytes(value.as_bytes())
+    }
+
+    pub fn set_bytes(&mut self, value: &[u8]) -> Result<(), std::io::Error> {
+        let len = value.len() as u64;
+        self.step(types::BYTES, lenenc_int_len(len) + value.len())?;
+        write_lenenc_int(len, self.buf);
+        self.buf.put_slice(value);
+        Ok(())
+    }
+
+    /// write the next field, the value type must match the data type of the field.
+    pub fn set(&mut self, value: Value) -> Result<(), std::io::Error> {
+        match value {
+            Value::Bool(v) => self.set_bool(v),
+            Value::I8(v) => self.set_i8(v),
+            Value::U8(v) => self.set_u8(v),
+            Value::I16(v) => self.set_i16(v),
+            Value::U16(v) => self.set_u16(v),
+            Value::I32(v) => self.set_i32(v),
+            Value::U32(v) => self.set_u32(v),
+            Value::I64(v) => self.set_i64(v),
+            Value::U64(v) => self.set_u64(v),
+            Value::F32(v) => self.set_f32(v),
+            Value::F64(v) => self.set_f64(v),
+            Value::Bytes(v) => self.set_bytes(v),
+            Value::Str(v) => self.set_str(v),
+        }
+    }
+}